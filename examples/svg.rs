@@ -10,5 +10,5 @@ fn main() {
     let file_name = "other/svg.svg";
     let _x = std::fs::write(file_name, s);
     println!("File saved: {}", file_name);
-    println!("You can open it in the browser.{}", "");
+    println!("You can open it in the browser.");
 }
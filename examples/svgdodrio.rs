@@ -6,5 +6,5 @@ fn main() {
     let file_name = "other/svgdodrio.svg";
     let _x = std::fs::write(file_name, s);
     println!("File saved: {}", file_name);
-    println!("If you want to open it in the browser, vou must add the svg and rect elements manually in the file.{}", "");
+    println!("If you want to open it in the browser, vou must add the svg and rect elements manually in the file.");
 }
@@ -5,21 +5,31 @@
 use crate::*;
 
 use bitvec::prelude::*;
+use std::fmt;
 
 /// Builder for a QR code.
 pub struct QrBuilder {
     /// Version to use.
     /// If not set a minimal version will be calculated.
-    pub version: Version,
+    pub version: Option<Version>,
+    /// Add an ECI designator for UTF-8 when byte mode data isn't in ISO-8859-1.
+    /// Default is true.
+    pub eci: bool,
+    /// Smallest version to consider when calculating a minimal version.
+    pub min_version: Version,
+    /// Largest version to consider when calculating a minimal version.
+    pub max_version: Version,
     /// Mask to use.
     /// If not set the optimal mask will be chosen per the QR specification.
     pub mask: Option<Mask>,
     /// Error correction level to use.
-    /// If not set ECLevel::Q, which recovers 25% of data, will be used.
+    /// If not set ECLevel::L, which recovers 7% of data, will be used.
     pub ecl: ECLevel,
     /// Encoding mode to use.
-    /// If not set will be inferred from input data.
-    pub mode: Mode,
+    /// If not set the input data is split into segments with the modes that gives the fewest bits.
+    pub mode: Option<Mode>,
+    /// Structured append header, if the QR code is part of a sequence.
+    pub structured_append: Option<StructuredAppend>,
 
     /// Resulting matrix.
     ///
@@ -33,19 +43,58 @@ pub enum Error {
     /// Mode doesn't support encoding the supplied message.
     UnsupportedMode,
     /// Message is too long for the supplied version, or larger than the max len.
-    MessageTooLong,
+    MessageTooLong {
+        /// Bytes needed to encode the message.
+        needed: usize,
+        /// Bytes available for data in the version.
+        available: usize,
+    },
     /// The builder was in an incomplete state when trying to create a QR.
     IncompleteBuilder,
+    /// Error correction level isn't supported by the version.
+    UnsupportedECLevel,
+    /// A logo covers more than the error correction can recover, or covers function patterns.
+    LogoTooLarge,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnsupportedMode => write!(f, "mode doesn't support encoding the supplied message"),
+            Error::MessageTooLong { needed, available } => {
+                write!(
+                    f,
+                    "message is too long, needs {} bytes but only {} are available",
+                    needed, available
+                )
+            }
+            Error::IncompleteBuilder => write!(f, "the builder was incomplete when trying to create a QR"),
+            Error::UnsupportedECLevel => write!(f, "error correction level isn't supported by the version"),
+            Error::LogoTooLarge => write!(f, "logo covers more than the error correction can recover, or function patterns"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Default for QrBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl QrBuilder {
     /// Create a new builder.
     pub fn new() -> QrBuilder {
         QrBuilder {
-            version: Version::new(3),
+            version: None,
+            eci: true,
+            min_version: Version::new(1),
+            max_version: Version::new(40),
             mask: None,
             ecl: ECLevel::L,
-            mode: Mode::Byte,
+            mode: None,
+            structured_append: None,
 
             matrix: Matrix::new(0),
         }
@@ -55,7 +104,23 @@ impl QrBuilder {
     pub fn version(mut self, v: Version) -> Self {
         // Override old tmp matrix.
         self.matrix = Matrix::new(v.size());
-        self.version = v;
+        self.version = Some(v);
+        self
+    }
+
+    /// Limit the versions considered when the smallest applicable version is calculated.
+    /// Useful to keep a stable size while still growing when needed.
+    pub fn version_range(mut self, min: Version, max: Version) -> Self {
+        assert!(min.0 <= max.0);
+        self.min_version = min;
+        self.max_version = max;
+        self
+    }
+
+    /// Set if an ECI designator for UTF-8 should be added when byte mode data isn't in ISO-8859-1.
+    /// Without it most scanners guess the encoding. Will default to true.
+    pub fn eci(mut self, v: bool) -> Self {
+        self.eci = v;
         self
     }
 
@@ -65,15 +130,23 @@ impl QrBuilder {
         self
     }
 
-    /// Set error correction. Will default to ECLevel::Q.
+    /// Set error correction. Will default to ECLevel::L.
     pub fn ecl(mut self, ecl: ECLevel) -> Self {
         self.ecl = ecl;
         self
     }
 
     /// Set the mode to use, will otherwise be calculated from input.
+    /// The whole input is then encoded as a single segment with this mode.
     pub fn mode(mut self, mode: Mode) -> Self {
-        self.mode = mode;
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Set the structured append header, placing the QR code in a sequence.
+    /// See QrSequence for splitting data over several QR codes.
+    pub fn structured_append(mut self, header: StructuredAppend) -> Self {
+        self.structured_append = Some(header);
         self
     }
 
@@ -83,6 +156,12 @@ impl QrBuilder {
         self.into_qr()
     }
 
+    /// Build all elements from precomputed segments and generate a QR code.
+    pub fn into_segments(mut self, segments: &[Segment]) -> Result<Qr, Error> {
+        self.add_all_segments(segments)?;
+        self.into_qr()
+    }
+
     /// Convert the builder into a QR code.
     pub fn into_qr(self) -> Result<Qr, Error> {
         if !self.complete() {
@@ -92,18 +171,24 @@ impl QrBuilder {
         Ok(Qr {
             matrix: self.matrix,
 
-            version: self.version,
+            version: self.version.unwrap(),
             ecl: self.ecl,
-            mode: self.mode,
+            mode: self.mode.unwrap(),
             mask: self.mask.unwrap(),
         })
     }
 
     /// Add all elements of a QR code.
     pub fn add_all(&mut self, s: &str) -> Result<(), Error> {
-        self.ensure_settings()?;
+        let segments = self.split(s)?;
+        self.add_all_segments(&segments)
+    }
+
+    /// Add all elements of a QR code, with data from precomputed segments.
+    pub fn add_all_segments(&mut self, segments: &[Segment]) -> Result<(), Error> {
+        self.ensure_settings(segments)?;
         self.add_fun_patterns();
-        self.add_data(s)?;
+        self.add_segments(segments)?;
         self.mask_data();
         self.add_info();
 
@@ -121,22 +206,31 @@ impl QrBuilder {
 
     /// Add data.
     pub fn add_data(&mut self, s: &str) -> Result<(), Error> {
-        self.ensure_settings()?;
+        let segments = self.split(s)?;
+        self.add_segments(&segments)
+    }
 
-        let version = self.version;
-        let mode = self.mode;
+    /// Add data from precomputed segments.
+    pub fn add_segments(&mut self, segments: &[Segment]) -> Result<(), Error> {
+        self.ensure_settings(segments)?;
 
-        let v = data::encode_with_mode(s, mode, version);
-        let v = ec::add(v, self.version);
+        let version = self.version.unwrap();
+        let ecl = self.ecl;
+
+        let v = match &self.structured_append {
+            Some(header) => data::encode_structured_append(header, segments, version, ecl)?,
+            None => data::encode_segments(segments, version, ecl)?,
+        };
+        let v = ec::add(v, version, ecl)?;
         self.add_raw_data(&v);
 
         Ok(())
     }
 
     /// Add raw data.
-    pub fn add_raw_data(&mut self, v: &BitVec<Lsb0, u8>) {
+    pub fn add_raw_data(&mut self, v: &BitVec<Msb0, u8>) {
         let mut vi = 0;
-        for (x, y) in ZigZagIt::new(self.matrix.size) {
+        for (x, y) in ZigZagIt::new(self.matrix.size()) {
             if self.matrix.is_fun(x, y) {
                 continue;
             }
@@ -171,38 +265,126 @@ impl QrBuilder {
     /// Add info.
     pub fn add_info(&mut self) {
         self.add_format_info();
+        self.add_version_info();
     }
 
     /// Add format info.
     pub fn add_format_info(&mut self) {
         // Hard assumption that we have necessary data.
-        let format = info::format_info(self.mask.unwrap());
+        let format = info::format_info(self.ecl, self.mask.unwrap());
         self.add_format(&format);
     }
 
+    /// Add version info.
+    /// Only versions 7 and up contains version info.
+    pub fn add_version_info(&mut self) {
+        // Hard assumption that we have necessary data.
+        if let Some(v) = info::version_info(self.version.unwrap()) {
+            self.add_version(&v);
+        }
+    }
+
     /// Return true if the build is complete.
     fn complete(&self) -> bool {
+        if self.mode.is_none() {
+            return false;
+        }
+        if self.version.is_none() {
+            return false;
+        }
         if self.mask.is_none() {
             return false;
         }
-        if self.matrix.size == 0 {
+        if self.matrix.size() == 0 {
             return false;
         }
         self.matrix.complete()
     }
 
-    // Ensure we have required settings, otherwise decide from string.
-    fn ensure_settings(&mut self) -> Result<(), Error> {
+    // Split a string into segments.
+    // If the mode is set it's a single segment, otherwise the optimal segments are used.
+    // The optimal segments depends on the version, so the version is decided here if not set.
+    fn split(&mut self, s: &str) -> Result<Vec<Segment>, Error> {
+        if let Some(mode) = self.mode {
+            if !mode.matches(s) {
+                return Err(Error::UnsupportedMode);
+            }
+            return Ok(self.add_eci(vec![Segment::new(mode, s)]));
+        }
+        if let Some(v) = self.version {
+            return Ok(segment::optimal(s, v, self.eci));
+        }
+        for v in self.versions() {
+            let segments = segment::optimal(s, v, self.eci);
+            if self.bit_len(&segments, v) <= info::total_bits(v, self.ecl) {
+                self.version = Some(v);
+                return Ok(segments);
+            }
+        }
+        let segments = segment::optimal(s, self.max_version, self.eci);
+        Err(self.too_long(&segments))
+    }
+
+    // Error for segments that doesn't fit in the largest version to consider.
+    fn too_long(&self, segments: &[Segment]) -> Error {
+        let v = self.max_version;
+        Error::MessageTooLong {
+            needed: self.bit_len(segments, v).div_ceil(8),
+            available: info::total_codewords(v, self.ecl),
+        }
+    }
+
+    // Bits needed for the segments, including a structured append header.
+    fn bit_len(&self, segments: &[Segment], v: Version) -> usize {
+        let header_len = if self.structured_append.is_some() {
+            sequence::HEADER_LEN
+        } else {
+            0
+        };
+        header_len + segment::bit_len(segments, v)
+    }
+
+    // Add the UTF-8 ECI before the first byte segment, if any byte segment isn't in ISO-8859-1.
+    // It stays in effect for all following segments.
+    fn add_eci(&self, mut segments: Vec<Segment>) -> Vec<Segment> {
+        let is_byte = |x: &Segment| x.mode == Mode::Byte;
+        if self.eci && segments.iter().any(|x| is_byte(x) && !Mode::in_latin1(&x.data)) {
+            let first = segments.iter_mut().find(|x| is_byte(x)).unwrap();
+            first.eci = Some(segment::ECI_UTF8);
+        }
+        segments
+    }
+
+    // Ensure we have required settings, otherwise decide from segments.
+    fn ensure_settings(&mut self, segments: &[Segment]) -> Result<(), Error> {
+        if segments.iter().any(|x| !x.mode.matches(&x.data)) {
+            return Err(Error::UnsupportedMode);
+        }
+        if self.mode.is_none() {
+            self.mode = Some(segments.first().map(|x| x.mode).unwrap_or(Mode::Byte));
+        }
+        if self.version.is_none() {
+            let v = self
+                .versions()
+                .find(|v| self.bit_len(segments, *v) <= info::total_bits(*v, self.ecl));
+            self.version = Some(v.ok_or_else(|| self.too_long(segments))?);
+        }
+
         // Ensure the matrix is initialized.
-        if self.matrix.size == 0 {
-            self.matrix = Matrix::new(self.version.size());
+        if self.matrix.size() == 0 {
+            self.matrix = Matrix::new(self.version.unwrap().size());
         }
 
         Ok(())
     }
 
+    // Versions to consider when calculating a minimal version.
+    fn versions(&self) -> impl Iterator<Item = Version> {
+        (self.min_version.0..=self.max_version.0).map(Version::new)
+    }
+
     fn add_finders(&mut self) {
-        let size = self.matrix.size;
+        let size = self.matrix.size();
 
         self.add_finder(0, 0);
         self.add_separator(0, 7, 7, 7);
@@ -233,8 +415,7 @@ impl QrBuilder {
     }
 
     fn add_alignments(&mut self) {
-        //let locations = ALIGNMENT_LOCATIONS[self.version.index()];
-        let locations = &[6, 22];
+        let locations = ALIGNMENT_LOCATIONS[self.version.unwrap().index()];
         for x in locations.iter() {
             for y in locations.iter() {
                 self.try_add_alignment(*x, *y);
@@ -255,7 +436,7 @@ impl QrBuilder {
 
     fn add_timing_patterns(&mut self) {
         let offset = 6;
-        for i in offset..self.matrix.size - offset {
+        for i in offset..self.matrix.size() - offset {
             let v = i % 2 == 0;
             self.set_timing(i, offset, v);
             self.set_timing(offset, i, v);
@@ -272,12 +453,12 @@ impl QrBuilder {
     }
 
     fn add_dark_module(&mut self) {
-        let (x, y) = self.version.dark_module_pos();
+        let (x, y) = self.version.unwrap().dark_module_pos();
         self.matrix.set(x, y, Module::Function(true));
     }
 
     fn add_reserved_areas(&mut self) {
-        let size = self.matrix.size;
+        let size = self.matrix.size();
 
         // Around top left finder.
         // Avoid timing pattern.
@@ -293,7 +474,7 @@ impl QrBuilder {
         self.reserve_rect(8, size - 7, 8, size - 1);
 
         //// Larger versions needs two areas for version information.
-        if self.version.extra_version_areas() {
+        if self.version.unwrap().extra_version_areas() {
             self.reserve_rect(0, size - 11, 5, size - 9);
             self.reserve_rect(size - 11, 0, size - 9, 5);
         }
@@ -304,9 +485,9 @@ impl QrBuilder {
         self.matrix.set_rect(x0, y0, x1, y1, Module::Reserved);
     }
 
-    fn add_format(&mut self, bv: &BitVec<Lsb0, u8>) {
+    fn add_format(&mut self, bv: &BitVec<Msb0, u8>) {
         assert_eq!(bv.len(), 15);
-        let size = self.matrix.size;
+        let size = self.matrix.size();
 
         // Info surrounding the top left finder.
         let mut iter = bv.iter();
@@ -338,6 +519,21 @@ impl QrBuilder {
         assert_eq!(iter.next(), None);
     }
 
+    fn add_version(&mut self, bv: &BitVec<Msb0, u8>) {
+        assert_eq!(bv.len(), 18);
+        let size = self.matrix.size();
+
+        // The least significant bit is placed first, it's the last bit in the BitVec.
+        for (i, v) in bv.iter().rev().enumerate() {
+            let a = i / 3;
+            let b = size - 11 + i % 3;
+            // Above the bottom left finder.
+            self.matrix.set_fun(a, b, *v);
+            // To the left of the top right finder, transposed.
+            self.matrix.set_fun(b, a, *v);
+        }
+    }
+
     /// Convert to debug string.
     pub fn to_dbg_string(&self) -> String {
         rendercommons::to_dbg_string(&self.matrix)
@@ -350,8 +546,10 @@ impl QrBuilder {
 // Inside the 2 bit flow it alternates between the right and left field.
 // It also avoids the vertical timing pattern column completely,
 // but it does not automatically skip function patterns.
-struct ZigZagIt {
-    size: usize,
+pub(crate) struct ZigZagIt {
+    height: usize,
+    // Column of the vertical timing pattern, if it's inside the data area.
+    timing: Option<usize>,
     // Should we move horizontal next step?
     horizontal_next: bool,
     // Are we moving upwards?
@@ -364,13 +562,30 @@ struct ZigZagIt {
 }
 
 impl ZigZagIt {
-    fn new(size: usize) -> Self {
+    pub(crate) fn new(size: usize) -> Self {
+        Self::with_start(size - 1, size, Some(6))
+    }
+
+    // Micro QR codes have the vertical timing pattern in the leftmost column,
+    // outside of the data area.
+    pub(crate) fn new_micro(size: usize) -> Self {
+        Self::with_start(size - 1, size, None)
+    }
+
+    // rMQR codes start next to the vertical timing pattern in the rightmost column,
+    // the timing patterns in the other columns are skipped like function patterns.
+    pub(crate) fn new_rmqr(width: usize, height: usize) -> Self {
+        Self::with_start(width - 2, height, None)
+    }
+
+    fn with_start(x: usize, height: usize, timing: Option<usize>) -> Self {
         Self {
-            size: size,
+            height,
+            timing,
             horizontal_next: true,
             upwards: true,
-            x: size - 1,
-            y: size - 1,
+            x,
+            y: height - 1,
             valid: true,
         }
     }
@@ -386,14 +601,14 @@ impl ZigZagIt {
     fn move_horizontally(&mut self) {
         match self.x {
             0 => self.valid = false,
-            6 => self.x -= 2,
+            x if Some(x) == self.timing => self.x -= 2,
             _ => self.x -= 1,
         }
         self.horizontal_next = false;
     }
 
     fn move_vertically(&mut self) {
-        if (self.upwards && self.y == 0) || (!self.upwards && self.y == self.size - 1) {
+        if (self.upwards && self.y == 0) || (!self.upwards && self.y == self.height - 1) {
             // When we've reached the edge move in the other direction instead of zagging.
             self.upwards = !self.upwards;
             self.move_horizontally();
@@ -423,3 +638,47 @@ impl Iterator for ZigZagIt {
         res
     }
 }
+
+// Center coordinates of alignment patterns, in both x and y, for each version.
+static ALIGNMENT_LOCATIONS: [&[usize]; 40] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+    &[6, 30, 54],
+    &[6, 32, 58],
+    &[6, 34, 62],
+    &[6, 26, 46, 66],
+    &[6, 26, 48, 70],
+    &[6, 26, 50, 74],
+    &[6, 30, 54, 78],
+    &[6, 30, 56, 82],
+    &[6, 30, 58, 86],
+    &[6, 34, 62, 90],
+    &[6, 28, 50, 72, 94],
+    &[6, 26, 50, 74, 98],
+    &[6, 30, 54, 78, 102],
+    &[6, 28, 54, 80, 106],
+    &[6, 32, 58, 84, 110],
+    &[6, 30, 58, 86, 114],
+    &[6, 34, 62, 90, 118],
+    &[6, 26, 50, 74, 98, 122],
+    &[6, 30, 54, 78, 102, 126],
+    &[6, 26, 52, 78, 104, 130],
+    &[6, 30, 56, 82, 108, 134],
+    &[6, 34, 60, 86, 112, 138],
+    &[6, 30, 58, 86, 114, 142],
+    &[6, 34, 62, 90, 118, 146],
+    &[6, 30, 54, 78, 102, 126, 150],
+    &[6, 24, 50, 76, 102, 128, 154],
+    &[6, 28, 54, 80, 106, 132, 158],
+    &[6, 32, 58, 84, 110, 136, 162],
+    &[6, 26, 54, 82, 110, 138, 166],
+    &[6, 30, 58, 86, 114, 142, 170],
+];
@@ -1,29 +1,94 @@
 //! Data encoding.
+use crate::builder::Error;
+use crate::ec::ECLevel;
 use crate::info;
-use crate::mode::Mode;
+use crate::micro::MicroVersion;
+use crate::mode::{self, Mode};
+use crate::rmqr::RmqrVersion;
+use crate::segment::{self, Segment};
+use crate::sequence::StructuredAppend;
+use crate::sjis;
 use crate::version::Version;
 
 use bitvec::prelude::*;
 use std::cmp;
 
-/// Encode string data to BitVec<Lsb0 , u8>.
+/// Encode string data to BitVec<Msb0 , u8>.
 /// Does not include error correction codes, it only encodes the data portion.
-pub fn encode(s: &str, version: Version) -> (Mode, BitVec<Lsb0, u8>) {
+pub fn encode(s: &str, version: Version, ecl: ECLevel) -> Result<(Mode, BitVec<Msb0, u8>), Error> {
     let mode = Mode::from_str(s);
-    let encoded = encode_with_mode(s, mode, version);
-    (mode, encoded)
+    let encoded = encode_with_mode(s, mode, version, ecl)?;
+    Ok((mode, encoded))
 }
 
-/// Encode string data to BitVec<Lsb0 , u8> in a specific mode.
+/// Encode string data to BitVec<Msb0 , u8> in a specific mode.
 /// Does not include error correction codes, it only encodes the data portion.
-pub fn encode_with_mode(s: &str, mode: Mode, version: Version) -> BitVec<Lsb0, u8> {
-    let total_capacity = info::total_bits();
-
-    // Encoding is mode, char count, data.
-    let mut bv = mode.to_bitvec();
-    bv.reserve(total_capacity);
-    bv.append(&mut bitvec_char_count(s.len(), mode, version));
-    bv.append(&mut bitvec_data(s, mode));
+pub fn encode_with_mode(s: &str, mode: Mode, version: Version, ecl: ECLevel) -> Result<BitVec<Msb0, u8>, Error> {
+    encode_segments(&[Segment::new(mode, s)], version, ecl)
+}
+
+/// Encode segments to BitVec<Msb0 , u8>, each segment in its own mode.
+/// Does not include error correction codes, it only encodes the data portion.
+/// ```
+/// # use qrcode53bytes::*;
+/// let segments = [Segment::new(Mode::Byte, &"x".repeat(60))];
+/// let res = data::encode_segments(&segments, Version::new(3), ECLevel::L);
+/// assert_eq!(res, Err(Error::MessageTooLong { needed: 62, available: 55 }));
+/// ```
+pub fn encode_segments(segments: &[Segment], version: Version, ecl: ECLevel) -> Result<BitVec<Msb0, u8>, Error> {
+    encode_with_header(BitVec::new(), segments, version, ecl)
+}
+
+/// Encode segments to BitVec<Msb0 , u8>, after a structured append header.
+/// Does not include error correction codes, it only encodes the data portion.
+pub fn encode_structured_append(
+    header: &StructuredAppend,
+    segments: &[Segment],
+    version: Version,
+    ecl: ECLevel,
+) -> Result<BitVec<Msb0, u8>, Error> {
+    encode_with_header(header.to_bitvec(), segments, version, ecl)
+}
+
+fn encode_with_header(
+    mut header: BitVec<Msb0, u8>,
+    segments: &[Segment],
+    version: Version,
+    ecl: ECLevel,
+) -> Result<BitVec<Msb0, u8>, Error> {
+    let total_capacity = info::total_bits(version, ecl);
+    let needed = header.len() + segment::bit_len(segments, version);
+    let too_long = Error::MessageTooLong {
+        needed: needed.div_ceil(8),
+        available: total_capacity / 8,
+    };
+    if needed > total_capacity {
+        return Err(too_long);
+    }
+
+    // Encoding of each segment is an optional ECI designator, mode, char count, data.
+    let mut bv = BitVec::<Msb0, u8>::with_capacity(total_capacity);
+    bv.append(&mut header);
+    let mut utf8 = segment::utf8_default(segments);
+    for segment in segments.iter() {
+        if let Some(eci) = segment.eci {
+            bv.append(&mut bitvec_eci(eci));
+            utf8 = eci == segment::ECI_UTF8;
+        }
+        let mode = segment.mode;
+        let s = segment.data.as_str();
+        if !mode.matches(s) {
+            return Err(Error::UnsupportedMode);
+        }
+        // A char count that doesn't fit would be truncated.
+        let count = segment.char_count(utf8);
+        if count >> version.char_count_len(mode) != 0 {
+            return Err(too_long);
+        }
+        bv.append(&mut mode.to_bitvec());
+        bv.append(&mut bitvec_char_count(count, mode, version));
+        bv.append(&mut bitvec_data(s, mode, utf8));
+    }
     assert!(bv.len() <= total_capacity);
 
     // Add up to 4 zero bits if we're below capacity.
@@ -34,7 +99,7 @@ pub fn encode_with_mode(s: &str, mode: Mode, version: Version) -> BitVec<Lsb0, u
     // If we're still below capacity add zero bits until we have full bytes.
     let zero_bits = (total_capacity - bv.len()) % 8;
     append(&mut bv, 0, zero_bits);
-    assert!(bv.len() % 8 == 0);
+    assert_eq!(bv.len() % 8, 0);
 
     // Until we reach our capacity add pad bytes.
     for pad in [0xEC, 0x11].iter().cycle() {
@@ -45,37 +110,189 @@ pub fn encode_with_mode(s: &str, mode: Mode, version: Version) -> BitVec<Lsb0, u
     }
     assert_eq!(bv.len(), total_capacity);
 
-    bv
+    Ok(bv)
+}
+
+/// Encode string data to BitVec<Msb0 , u8> for a Micro QR code, in a specific mode.
+/// Does not include error correction codes, it only encodes the data portion.
+/// M1 and M3 ends with a 4 bit codeword, so the len isn't always a whole number of bytes.
+pub fn encode_micro(s: &str, mode: Mode, version: MicroVersion, ecl: ECLevel) -> Result<BitVec<Msb0, u8>, Error> {
+    let total_capacity = info::micro_data_bits(version, ecl).ok_or(Error::UnsupportedECLevel)?;
+    let count_len = version.char_count_len(mode).ok_or(Error::UnsupportedMode)?;
+    if !mode.matches(s) {
+        return Err(Error::UnsupportedMode);
+    }
+
+    let count = mode.char_count(s);
+    let mut bv = mode.to_micro_bitvec(version);
+    append(&mut bv, count as u32, count_len);
+    bv.append(&mut bitvec_data(s, mode, false));
+    // A char count that doesn't fit would be truncated.
+    if bv.len() > total_capacity || count >> count_len != 0 {
+        return Err(Error::MessageTooLong {
+            needed: bv.len().div_ceil(8),
+            available: total_capacity / 8,
+        });
+    }
+
+    // The terminator is 3, 5, 7 or 9 zero bits, but may be cut short at capacity.
+    let zero_bits = cmp::min(total_capacity - bv.len(), version.terminator_len());
+    append(&mut bv, 0, zero_bits);
+
+    // Zero bits until a full codeword, the last codeword may be only 4 bits.
+    let zero_bits = cmp::min(bv.len().div_ceil(8) * 8, total_capacity) - bv.len();
+    append(&mut bv, 0, zero_bits);
+
+    // Add pad bytes for each remaining full codeword.
+    for pad in [0xEC, 0x11].iter().cycle() {
+        if bv.len() + 8 > total_capacity {
+            break;
+        }
+        append(&mut bv, *pad, 8);
+    }
+
+    // A remaining 4 bit codeword is zero.
+    let zero_bits = total_capacity - bv.len();
+    append(&mut bv, 0, zero_bits);
+    assert_eq!(bv.len(), total_capacity);
+
+    Ok(bv)
+}
+
+/// Encode string data to BitVec<Msb0 , u8> for an rMQR code, in a specific mode.
+/// Does not include error correction codes, it only encodes the data portion.
+pub fn encode_rmqr(s: &str, mode: Mode, version: RmqrVersion, ecl: ECLevel) -> Result<BitVec<Msb0, u8>, Error> {
+    let total_capacity = 8 * info::rmqr_total_codewords(version, ecl).ok_or(Error::UnsupportedECLevel)?;
+    if !mode.matches(s) {
+        return Err(Error::UnsupportedMode);
+    }
+
+    let count = mode.char_count(s);
+    let count_len = version.char_count_len(mode);
+    let mut bv = mode.to_rmqr_bitvec();
+    append(&mut bv, count as u32, count_len);
+    bv.append(&mut bitvec_data(s, mode, false));
+    // A char count that doesn't fit would be truncated.
+    if bv.len() > total_capacity || count >> count_len != 0 {
+        return Err(Error::MessageTooLong {
+            needed: bv.len().div_ceil(8),
+            available: total_capacity / 8,
+        });
+    }
+
+    // Add up to 3 zero bits if we're below capacity.
+    let zero_bits = cmp::min(total_capacity - bv.len(), 3);
+    append(&mut bv, 0, zero_bits);
+
+    // If we're still below capacity add zero bits until we have full bytes.
+    let zero_bits = (total_capacity - bv.len()) % 8;
+    append(&mut bv, 0, zero_bits);
+
+    // Until we reach our capacity add pad bytes.
+    for pad in [0xEC, 0x11].iter().cycle() {
+        if bv.len() >= total_capacity {
+            break;
+        }
+        append(&mut bv, *pad, 8);
+    }
+    assert_eq!(bv.len(), total_capacity);
+
+    Ok(bv)
 }
 
 /// Append data to bitvec of a certain len.
-pub fn append(bv: &mut BitVec<Lsb0, u8>, v: u32, len: usize) {
+pub fn append(bv: &mut BitVec<Msb0, u8>, v: u32, len: usize) {
     bv.extend((0..len).rev().map(|i| (v >> i) & 1 != 0));
 }
 
-fn bitvec_char_count(len: usize, mode: Mode, v: Version) -> BitVec<Lsb0, u8> {
-    let mut bv = BitVec::<Lsb0, u8>::new();
+fn bitvec_char_count(len: usize, mode: Mode, v: Version) -> BitVec<Msb0, u8> {
+    let mut bv = BitVec::<Msb0, u8>::new();
     append(&mut bv, len as u32, v.char_count_len(mode));
     bv
 }
 
-fn bitvec_data(s: &str, mode: Mode) -> BitVec<Lsb0, u8> {
-    let bytes = string_to_bytes(s, mode);
+// The ECI mode indicator followed by the designator in 8, 16 or 24 bits.
+fn bitvec_eci(eci: u32) -> BitVec<Msb0, u8> {
+    let mut bv = bitvec![Msb0, u8; 0, 1, 1, 1];
+    if eci < 1 << 7 {
+        append(&mut bv, eci, 8);
+    } else if eci < 1 << 14 {
+        append(&mut bv, 0b10, 2);
+        append(&mut bv, eci, 14);
+    } else {
+        append(&mut bv, 0b110, 3);
+        append(&mut bv, eci, 21);
+    }
+    bv
+}
+
+fn bitvec_data(s: &str, mode: Mode, utf8: bool) -> BitVec<Msb0, u8> {
+    let bytes = string_to_bytes(s, mode, utf8);
 
     match mode {
+        Mode::Numeric => encode_numeric_data(&bytes),
+        Mode::Alphanumeric => encode_alphanumeric_data(&bytes),
+        Mode::Kanji => encode_kanji_data(&bytes),
         Mode::Byte => encode_byte_data(&bytes),
     }
 }
 
-fn encode_byte_data(v: &Vec<u8>) -> BitVec<Lsb0, u8> {
-    // It's already in ISO 8859-1, or UTF-8
-    BitVec::<Lsb0, u8>::from_slice(&v[..])
+fn encode_numeric_data(v: &[u8]) -> BitVec<Msb0, u8> {
+    let mut bv = BitVec::<Msb0, u8>::with_capacity(v.len() * 4);
+    // Groups of 3 digits are encoded as a 10 bit number.
+    // If there's a shorter group at the end 2 digits use 7 bits and 1 digit 4 bits.
+    for group in v.chunks(3) {
+        let num = group.iter().fold(0, |acc, x| acc * 10 + *x as u32);
+        let len = match group.len() {
+            3 => 10,
+            2 => 7,
+            _ => 4,
+        };
+        append(&mut bv, num, len);
+    }
+    bv
+}
+
+fn encode_alphanumeric_data(v: &[u8]) -> BitVec<Msb0, u8> {
+    let mut bv = BitVec::<Msb0, u8>::with_capacity(v.len() * 6);
+    // Pairs of chars are encoded as 45 * first + second in 11 bits.
+    // A remaining char at the end uses 6 bits.
+    for pair in v.chunks(2) {
+        if pair.len() == 2 {
+            append(&mut bv, 45 * pair[0] as u32 + pair[1] as u32, 11);
+        } else {
+            append(&mut bv, pair[0] as u32, 6);
+        }
+    }
+    bv
+}
+
+fn encode_kanji_data(v: &[u8]) -> BitVec<Msb0, u8> {
+    let mut bv = BitVec::<Msb0, u8>::with_capacity(v.len() / 2 * 13);
+    // Each double byte char is encoded in 13 bits.
+    for pair in v.chunks(2) {
+        let code = (pair[0] as u32) << 8 | pair[1] as u32;
+        let code = if code <= 0x9FFC { code - 0x8140 } else { code - 0xC140 };
+        append(&mut bv, (code >> 8) * 0xC0 + (code & 0xFF), 13);
+    }
+    bv
+}
+
+fn encode_byte_data(v: &[u8]) -> BitVec<Msb0, u8> {
+    // It's already in ISO-8859-1, or UTF-8
+    BitVec::<Msb0, u8>::from_slice(v)
 }
 
 // Converts string to byte representation.
 // Numeric and alphanumeric are compacted more.
-fn string_to_bytes(s: &str, mode: Mode) -> Vec<u8> {
+// Byte mode uses UTF-8 if the UTF-8 ECI is in effect or if ISO-8859-1 isn't possible.
+fn string_to_bytes(s: &str, mode: Mode, utf8: bool) -> Vec<u8> {
     match mode {
-        Mode::Byte => s.bytes().collect(),
+        Mode::Numeric => s.bytes().map(|x| x - b'0').collect(),
+        Mode::Alphanumeric => s.chars().map(|x| mode::alphanumeric_value(x).unwrap()).collect(),
+        // Double byte Shift JIS, most significant byte first.
+        Mode::Kanji => s.chars().flat_map(|x| sjis::from_char(x).unwrap().to_be_bytes()).collect(),
+        Mode::Byte if utf8 || !Mode::in_latin1(s) => s.bytes().collect(),
+        Mode::Byte => s.chars().map(|x| x as u8).collect(),
     }
 }
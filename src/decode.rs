@@ -0,0 +1,341 @@
+//! Decoding, from a matrix back to the data.
+//!
+//! The reverse of the builder: read the format info, unmask,
+//! read the codewords in the same zig-zag order, correct errors in each block
+//! with the error correction codewords and parse the segments.
+
+use crate::builder::{QrBuilder, ZigZagIt};
+use crate::ec::{self, ECLevel};
+use crate::info;
+use crate::mask::{self, Mask};
+use crate::matrix::{Matrix, Module};
+use crate::mode::{self, Mode};
+use crate::segment::{self, Segment};
+use crate::sequence::StructuredAppend;
+use crate::sjis;
+use crate::version::Version;
+
+use bitvec::prelude::*;
+use std::fmt;
+
+/// Decoding error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// No QR code was found in the image.
+    NotFound,
+    /// The image len doesn't match its width and height.
+    InvalidImage,
+    /// The matrix doesn't have the size of a QR code version.
+    InvalidSize,
+    /// Neither copy of the format info could be read.
+    InvalidFormatInfo,
+    /// A block has more errors than the error correction codewords can correct.
+    TooManyErrors,
+    /// The data doesn't follow the QR specification.
+    InvalidData,
+    /// Byte mode data uses an ECI designator that isn't supported.
+    UnsupportedECI(u32),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::NotFound => write!(f, "no QR code was found in the image"),
+            DecodeError::InvalidImage => write!(f, "the image len doesn't match its width and height"),
+            DecodeError::InvalidSize => write!(f, "the matrix doesn't have the size of a QR code"),
+            DecodeError::InvalidFormatInfo => write!(f, "the format info couldn't be read"),
+            DecodeError::TooManyErrors => write!(f, "the data has too many errors"),
+            DecodeError::InvalidData => write!(f, "the data doesn't follow the QR specification"),
+            DecodeError::UnsupportedECI(eci) => write!(f, "ECI designator {} isn't supported", eci),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// The result of decoding a QR code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// Version of the QR code.
+    pub version: Version,
+
+    /// Error correction level.
+    pub ecl: ECLevel,
+
+    /// The applied mask.
+    pub mask: Mask,
+
+    /// The segments, in order.
+    pub segments: Vec<Segment>,
+
+    /// Structured append header, if the QR code is part of a sequence.
+    pub structured_append: Option<StructuredAppend>,
+}
+
+impl Decoded {
+    /// Returns the data of all segments.
+    pub fn data(&self) -> String {
+        self.segments.iter().map(|x| x.data.as_str()).collect()
+    }
+}
+
+/// Decode a QR code matrix.
+///
+/// Only the dark or light value of each module is used,
+/// so the matrix may come from a renderer as well as from a scanner.
+/// ```
+/// # use qrcode53bytes::*;
+/// let qr = QrBuilder::new().ecl(ECLevel::Q).into("https://bestia.dev/?id=12345678901234567890").unwrap();
+/// let decoded = decode::decode_matrix(&qr.matrix).unwrap();
+/// assert_eq!(decoded.data(), "https://bestia.dev/?id=12345678901234567890");
+/// assert_eq!((decoded.version, decoded.ecl, decoded.mask), (qr.version, qr.ecl, qr.mask));
+/// ```
+pub fn decode_matrix(matrix: &Matrix) -> Result<Decoded, DecodeError> {
+    let size = matrix.width;
+    if matrix.height != size || !(21..=177).contains(&size) {
+        return Err(DecodeError::InvalidSize);
+    }
+    let version = Version::new((size - 17) / 4);
+    if version.size() != size {
+        return Err(DecodeError::InvalidSize);
+    }
+    let (ecl, mask) = read_format_info(matrix)?;
+
+    // Mark the modules outside the function patterns as data, so they can be unmasked.
+    let mut builder = QrBuilder::new().version(version);
+    builder.add_fun_patterns();
+    let mut data = builder.matrix;
+    for y in 0..size {
+        for x in 0..size {
+            let m = if data.is_fun(x, y) {
+                Module::Function(matrix.is_dark(x, y))
+            } else {
+                Module::Data(matrix.is_dark(x, y))
+            };
+            data.set(x, y, m);
+        }
+    }
+    let data = mask::apply_mask(mask, &data);
+
+    let mut bv = BitVec::<Msb0, u8>::with_capacity(size * size);
+    for (x, y) in ZigZagIt::new(size) {
+        if data.is_data(x, y) {
+            bv.push(data.is_dark(x, y));
+        }
+    }
+
+    let layout = info::group_block_count(version, ecl);
+    let ec_count = info::block_ec_count(version, ecl);
+    let total: usize = layout.iter().sum::<usize>() + ec_count * layout.len();
+    // Remainder bits at the end are ignored.
+    bv.truncate(total * 8);
+    let blocks = deinterleave(bv.as_slice(), &layout, ec_count);
+
+    let mut codewords = Vec::with_capacity(total);
+    for (block, len) in blocks.iter().zip(layout.iter()) {
+        let corrected = ec::correct(block, ec_count).map_err(|_| DecodeError::TooManyErrors)?;
+        codewords.extend_from_slice(&corrected[..*len]);
+    }
+
+    let (segments, structured_append) = parse(&BitVec::<Msb0, u8>::from_vec(codewords), version)?;
+    Ok(Decoded {
+        version,
+        ecl,
+        mask,
+        segments,
+        structured_append,
+    })
+}
+
+// Read the format info, from the second copy if the first has too many errors.
+fn read_format_info(matrix: &Matrix) -> Result<(ECLevel, Mask), DecodeError> {
+    let size = matrix.width;
+    let read = |coords: &mut dyn Iterator<Item = (usize, usize)>| coords.fold(0u16, |acc, (x, y)| acc << 1 | matrix.is_dark(x, y) as u16);
+
+    // Surrounding the top left finder, avoiding the timing patterns.
+    let mut first = (0..8)
+        .filter(|x| *x != 6)
+        .map(|x| (x, 8))
+        .chain((0..9).rev().filter(|y| *y != 6).map(|y| (8, y)));
+    // To the right of the bottom left finder, then below the top right finder.
+    let mut second = (size - 7..size).rev().map(|y| (8, y)).chain((size - 8..size).map(|x| (x, 8)));
+
+    info::decode_format_info(read(&mut first))
+        .or_else(|| info::decode_format_info(read(&mut second)))
+        .ok_or(DecodeError::InvalidFormatInfo)
+}
+
+// Split interleaved codewords into blocks, each with its data and error correction codewords.
+fn deinterleave(codewords: &[u8], layout: &[usize], ec_count: usize) -> Vec<Vec<u8>> {
+    let mut blocks: Vec<Vec<u8>> = layout.iter().map(|x| Vec::with_capacity(x + ec_count)).collect();
+    let mut it = codewords.iter();
+
+    let layout_max = layout.iter().max().unwrap();
+    for i in 0..*layout_max {
+        for (block, len) in blocks.iter_mut().zip(layout.iter()) {
+            if i < *len {
+                block.push(*it.next().unwrap());
+            }
+        }
+    }
+    for _ in 0..ec_count {
+        for block in blocks.iter_mut() {
+            block.push(*it.next().unwrap());
+        }
+    }
+    assert_eq!(it.next(), None);
+    blocks
+}
+
+// Parse the data codewords into segments.
+fn parse(bv: &BitVec<Msb0, u8>, v: Version) -> Result<(Vec<Segment>, Option<StructuredAppend>), DecodeError> {
+    let mut reader = Reader { bv, pos: 0 };
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut structured_append = None;
+    // An ECI designator stays in effect for the following segments.
+    let mut eci = None;
+    let mut active_eci = None;
+
+    // The terminator may be shortened or left out when the data is full.
+    while reader.remaining() >= 4 {
+        let mode = match reader.read(4)? {
+            0b0000 => break,
+            0b0111 => {
+                eci = Some(read_eci(&mut reader)?);
+                active_eci = eci;
+                continue;
+            }
+            0b0011 if segments.is_empty() && structured_append.is_none() => {
+                let index = reader.read(4)? as usize;
+                let total = reader.read(4)? as usize + 1;
+                let parity = reader.read(8)? as u8;
+                if index >= total {
+                    return Err(DecodeError::InvalidData);
+                }
+                structured_append = Some(StructuredAppend::new(index, total, parity));
+                continue;
+            }
+            0b0001 => Mode::Numeric,
+            0b0010 => Mode::Alphanumeric,
+            0b0100 => Mode::Byte,
+            0b1000 => Mode::Kanji,
+            _ => return Err(DecodeError::InvalidData),
+        };
+
+        let count = reader.read(v.char_count_len(mode))? as usize;
+        let data = match mode {
+            Mode::Numeric => read_numeric(&mut reader, count)?,
+            Mode::Alphanumeric => read_alphanumeric(&mut reader, count)?,
+            Mode::Kanji => read_kanji(&mut reader, count)?,
+            Mode::Byte => read_byte(&mut reader, count, active_eci)?,
+        };
+        let mut segment = Segment::new(mode, &data);
+        segment.eci = eci.take();
+        segments.push(segment);
+    }
+
+    Ok((segments, structured_append))
+}
+
+// The ECI designator, in 8, 16 or 24 bits.
+fn read_eci(reader: &mut Reader) -> Result<u32, DecodeError> {
+    let first = reader.read(8)?;
+    if first & 0x80 == 0 {
+        Ok(first)
+    } else if first & 0xC0 == 0x80 {
+        Ok((first & 0x3F) << 8 | reader.read(8)?)
+    } else if first & 0xE0 == 0xC0 {
+        Ok((first & 0x1F) << 16 | reader.read(16)?)
+    } else {
+        Err(DecodeError::InvalidData)
+    }
+}
+
+fn read_numeric(reader: &mut Reader, count: usize) -> Result<String, DecodeError> {
+    let mut s = String::with_capacity(count);
+    let mut left = count;
+    while left > 0 {
+        let (digits, len) = match left {
+            1 => (1, 4),
+            2 => (2, 7),
+            _ => (3, 10),
+        };
+        let num = reader.read(len)?;
+        if num >= 10u32.pow(digits as u32) {
+            return Err(DecodeError::InvalidData);
+        }
+        s.push_str(&format!("{:0width$}", num, width = digits));
+        left -= digits;
+    }
+    Ok(s)
+}
+
+fn read_alphanumeric(reader: &mut Reader, count: usize) -> Result<String, DecodeError> {
+    let mut s = String::with_capacity(count);
+    let char_of = |v: u32| mode::alphanumeric_char(v as u8).ok_or(DecodeError::InvalidData);
+    for _ in 0..count / 2 {
+        let pair = reader.read(11)?;
+        if pair >= 45 * 45 {
+            return Err(DecodeError::InvalidData);
+        }
+        s.push(char_of(pair / 45)?);
+        s.push(char_of(pair % 45)?);
+    }
+    if count % 2 == 1 {
+        s.push(char_of(reader.read(6)?)?);
+    }
+    Ok(s)
+}
+
+fn read_kanji(reader: &mut Reader, count: usize) -> Result<String, DecodeError> {
+    let mut s = String::with_capacity(count * 3);
+    for _ in 0..count {
+        let v = reader.read(13)?;
+        let code = (v / 0xC0) << 8 | (v % 0xC0);
+        let code = if code < 0x1F00 { code + 0x8140 } else { code + 0xC140 };
+        s.push(sjis::to_char(code as u16).ok_or(DecodeError::InvalidData)?);
+    }
+    Ok(s)
+}
+
+// Byte mode data is UTF-8 if the UTF-8 ECI is in effect and ISO-8859-1 with the ISO-8859-1 ECI.
+// Without an ECI it's ISO-8859-1, unless it's valid UTF-8 with non ASCII chars.
+// The encoder uses UTF-8 for all such data in a QR code if any of it isn't in ISO-8859-1.
+fn read_byte(reader: &mut Reader, count: usize, eci: Option<u32>) -> Result<String, DecodeError> {
+    let mut bytes = Vec::with_capacity(count);
+    for _ in 0..count {
+        bytes.push(reader.read(8)? as u8);
+    }
+    let latin1 = |bytes: Vec<u8>| bytes.iter().map(|x| *x as char).collect();
+    match eci {
+        Some(segment::ECI_UTF8) => String::from_utf8(bytes).map_err(|_| DecodeError::InvalidData),
+        Some(ECI_LATIN1) => Ok(latin1(bytes)),
+        Some(eci) => Err(DecodeError::UnsupportedECI(eci)),
+        None if bytes.is_ascii() => Ok(latin1(bytes)),
+        None => String::from_utf8(bytes).or_else(|e| Ok(latin1(e.into_bytes()))),
+    }
+}
+
+// Reads bits from a BitVec, most significant bit first.
+struct Reader<'a> {
+    bv: &'a BitVec<Msb0, u8>,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bv.len() - self.pos
+    }
+
+    fn read(&mut self, len: usize) -> Result<u32, DecodeError> {
+        if len > self.remaining() {
+            return Err(DecodeError::InvalidData);
+        }
+        let v = self.bv[self.pos..self.pos + len].iter().fold(0, |acc, x| acc << 1 | *x as u32);
+        self.pos += len;
+        Ok(v)
+    }
+}
+
+// ECI designator for ISO-8859-1.
+const ECI_LATIN1: u32 = 3;
@@ -1,6 +1,9 @@
 //! Error correction calculations.
 
+use crate::builder::Error;
 use crate::info;
+use crate::micro::MicroVersion;
+use crate::rmqr::RmqrVersion;
 use crate::version::Version;
 
 use bitvec::prelude::*;
@@ -10,13 +13,22 @@ use bitvec::prelude::*;
 pub enum ECLevel {
     /// Recovers 7% of data
     L = 0,
+    /// Recovers 15% of data
+    M = 1,
+    /// Recovers 25% of data
+    Q = 2,
+    /// Recovers 30% of data
+    H = 3,
 }
 
 impl ECLevel {
     /// Returns the bit encoding. It is not the same as the enum order.
-    pub fn to_bitvec(&self) -> BitVec<Lsb0, u8> {
+    pub fn to_bitvec(&self) -> BitVec<Msb0, u8> {
         match self {
-            ECLevel::L => bitvec![Lsb0, u8;0, 1],
+            ECLevel::L => bitvec![Msb0, u8;0, 1],
+            ECLevel::M => bitvec![Msb0, u8;0, 0],
+            ECLevel::Q => bitvec![Msb0, u8;1, 1],
+            ECLevel::H => bitvec![Msb0, u8;1, 0],
         }
     }
 }
@@ -25,11 +37,36 @@ impl ECLevel {
 ///
 /// This includes both the data and the error correction codewords,
 /// interleaved if necessary.
-pub fn add(data: BitVec<Lsb0, u8>, v: Version) -> BitVec<Lsb0, u8> {
-    let layout = info::group_block_count();
-    assert_eq!(data.len() / 8, layout.iter().sum());
+pub fn add(data: BitVec<Msb0, u8>, v: Version, ecl: ECLevel) -> Result<BitVec<Msb0, u8>, Error> {
+    let layout = info::group_block_count(v, ecl);
+    let ec_count = info::block_ec_count(v, ecl);
+    let remainder = REMAINDER_BITS[v.index()];
+    interleave(data, &layout, ec_count, remainder)
+}
+
+/// Add error correction codewords to rMQR code data.
+///
+/// Blocks are interleaved the same way as for QR codes.
+pub fn add_rmqr(data: BitVec<Msb0, u8>, v: RmqrVersion, ecl: ECLevel) -> Result<BitVec<Msb0, u8>, Error> {
+    let layout = info::rmqr_group_block_count(v, ecl).ok_or(Error::UnsupportedECLevel)?;
+    let ec_count = info::rmqr_block_ec_count(v, ecl).unwrap();
+    let remainder = RMQR_REMAINDER_BITS[v.0];
+    interleave(data, &layout, ec_count, remainder)
+}
+
+// Split data into blocks, add error correction codewords and interleave.
+fn interleave(data: BitVec<Msb0, u8>, layout: &[usize], ec_count: usize, remainder: usize) -> Result<BitVec<Msb0, u8>, Error> {
+    let total: usize = layout.iter().sum();
+    if data.len() > total * 8 {
+        return Err(Error::MessageTooLong {
+            needed: data.len().div_ceil(8),
+            available: total,
+        });
+    }
+    // The data should already be padded to the full capacity.
+    assert_eq!(data.len(), total * 8);
 
-    let blocks = group_into_blocks(&data, &layout);
+    let blocks = group_into_blocks(&data, layout);
     let mut bytes: Vec<u8> = Vec::with_capacity(data.len() / 8);
 
     // First interleave all codewords in blocks.
@@ -43,7 +80,6 @@ pub fn add(data: BitVec<Lsb0, u8>, v: Version) -> BitVec<Lsb0, u8> {
     }
 
     // Then interleave all ec codewords in blocks.
-    let ec_count = info::block_ec_count();
     let ec_blocks: Vec<Vec<u8>> = blocks
         .iter()
         .map(|x| generate_ec_codewords(x.as_slice(), ec_count))
@@ -54,25 +90,48 @@ pub fn add(data: BitVec<Lsb0, u8>, v: Version) -> BitVec<Lsb0, u8> {
         }
     }
 
-    let mut res: BitVec<Lsb0, u8> = BitVec::<Lsb0, u8>::from_vec(bytes);
+    let mut res: BitVec<Msb0, u8> = BitVec::<Msb0, u8>::from_vec(bytes);
 
     // Add padding remainder bits.
-    let remainder = REMAINDER_BITS[v.index()];
     res.resize(res.len() + remainder, false);
     assert_eq!(
         res.len(),
         data.len() + 8 * ec_count * layout.len() + remainder
     );
 
-    res
+    Ok(res)
+}
+
+/// Add error correction codewords to Micro QR code data.
+///
+/// Micro QR codes have a single block, so there's no interleaving.
+/// In M1 and M3 the last data codeword is only 4 bits, it's padded with zeros
+/// when calculating the error correction codewords.
+pub fn add_micro(data: BitVec<Msb0, u8>, v: MicroVersion, ecl: ECLevel) -> Result<BitVec<Msb0, u8>, Error> {
+    let data_bits = info::micro_data_bits(v, ecl).ok_or(Error::UnsupportedECLevel)?;
+    if data.len() > data_bits {
+        return Err(Error::MessageTooLong {
+            needed: data.len().div_ceil(8),
+            available: data_bits / 8,
+        });
+    }
+    // The data should already be padded to the full capacity.
+    assert_eq!(data.len(), data_bits);
+
+    let mut codewords = data.clone();
+    codewords.resize(data_bits.div_ceil(8) * 8, false);
+    let ec_count = info::micro_ec_count(v, ecl).unwrap();
+    let ec = generate_ec_codewords(codewords.as_slice(), ec_count);
+
+    let mut res = data;
+    res.append(&mut BitVec::<Msb0, u8>::from_vec(ec));
+    assert_eq!(res.len(), data_bits + 8 * ec_count);
+
+    Ok(res)
 }
 
-fn generate_ec_codewords(msg: &[u8], ec_count: usize) -> Vec<u8> {
-    //this is 15
-    //let gen = GEN_POLYS[ec_count];
-    let gen = &[
-        8, 183, 61, 91, 202, 37, 51, 58, 58, 237, 140, 124, 5, 99, 105,
-    ];
+pub(crate) fn generate_ec_codewords(msg: &[u8], ec_count: usize) -> Vec<u8> {
+    let gen = GEN_POLYS[ec_count];
     assert_eq!(gen.len(), ec_count);
 
     // res[i] corresponds to the constant before x^i.
@@ -101,7 +160,136 @@ fn generate_ec_codewords(msg: &[u8], ec_count: usize) -> Vec<u8> {
     v
 }
 
-fn group_into_blocks(bv: &BitVec<Lsb0, u8>, layout: &Vec<usize>) -> Vec<Vec<u8>> {
+/// Correct errors in a block, the data codewords followed by ec_count error correction codewords.
+///
+/// Returns the corrected block. Up to ec_count / 2 erroneous codewords can be corrected,
+/// if there are more the error is the number of errors found, which may be too low.
+/// ```
+/// # use qrcode53bytes::*;
+/// let mut block = vec![0x40, 0xd2, 0x75, 0x47, 0x76, 0x17, 0x32, 0x06, 0x27, 0x26, 0x96, 0xc6, 0xc6, 0x96, 0x70, 0xec];
+/// block.extend(vec![0xbc, 0x2a, 0x90, 0x13, 0x6b, 0xaf, 0xef, 0xfd, 0x4b, 0xe0]);
+/// let mut damaged = block.clone();
+/// damaged[0] = 0;
+/// damaged[10] ^= 0x55;
+/// assert_eq!(ec::correct(&damaged, 10), Ok(block));
+/// ```
+pub fn correct(block: &[u8], ec_count: usize) -> Result<Vec<u8>, usize> {
+    assert!(block.len() > ec_count);
+
+    // Evaluate the block polynomial at the roots of the generator polynomial.
+    // block[0] is the constant before the highest power.
+    let syndromes: Vec<u8> = (0..ec_count)
+        .map(|i| block.iter().fold(0, |acc, x| gf_mul(acc, EXP[i]) ^ x))
+        .collect();
+    if syndromes.iter().all(|x| *x == 0) {
+        return Ok(block.into());
+    }
+
+    let locator = error_locator(&syndromes);
+    let error_count = locator.len() - 1;
+    if error_count > ec_count / 2 {
+        return Err(error_count);
+    }
+
+    // Chien search, try all positions. An error at power j gives a root at 2^-j.
+    let positions: Vec<usize> = (0..block.len())
+        .filter(|j| poly_eval(&locator, EXP[(255 - j % 255) % 255]) == 0)
+        .collect();
+    if positions.len() != error_count {
+        return Err(error_count);
+    }
+
+    // Forney, the evaluator is syndromes * locator mod x^ec_count.
+    let mut evaluator = vec![0; ec_count];
+    for (i, s) in syndromes.iter().enumerate() {
+        for (j, l) in locator.iter().enumerate().take(ec_count - i) {
+            evaluator[i + j] ^= gf_mul(*s, *l);
+        }
+    }
+    // The formal derivative only keeps the odd powers.
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, x)| if i % 2 == 1 { *x } else { 0 })
+        .collect();
+
+    let mut res: Vec<u8> = block.into();
+    for j in positions {
+        let x = EXP[j % 255];
+        let x_inv = EXP[(255 - j % 255) % 255];
+        let denominator = poly_eval(&derivative, x_inv);
+        if denominator == 0 {
+            return Err(error_count);
+        }
+        let magnitude = gf_div(gf_mul(x, poly_eval(&evaluator, x_inv)), denominator);
+        let i = block.len() - 1 - j;
+        res[i] ^= magnitude;
+    }
+    Ok(res)
+}
+
+// Berlekamp-Massey, find the error locator polynomial from the syndromes.
+// The result has the constant before x^i at index i, its degree is the number of errors.
+fn error_locator(syndromes: &[u8]) -> Vec<u8> {
+    let mut c: Vec<u8> = vec![1];
+    let mut b: Vec<u8> = vec![1];
+    let mut len = 0;
+    let mut shift = 1;
+    let mut last_discrepancy = 1;
+
+    for n in 0..syndromes.len() {
+        let discrepancy = (1..=len).fold(syndromes[n], |acc, i| acc ^ gf_mul(c[i], syndromes[n - i]));
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        // c -= discrepancy / last_discrepancy * x^shift * b
+        let coef = gf_div(discrepancy, last_discrepancy);
+        let prev = c.clone();
+        if c.len() < b.len() + shift {
+            c.resize(b.len() + shift, 0);
+        }
+        for (i, x) in b.iter().enumerate() {
+            c[i + shift] ^= gf_mul(coef, *x);
+        }
+        if 2 * len <= n {
+            len = n + 1 - len;
+            b = prev;
+            last_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+    c.truncate(len + 1);
+    c.resize(len + 1, 0);
+    c
+}
+
+// Evaluate a polynomial with the constant before x^i at index i.
+fn poly_eval(poly: &[u8], x: u8) -> u8 {
+    poly.iter().rev().fold(0, |acc, c| gf_mul(acc, x) ^ c)
+}
+
+// Multiplication in GF(256).
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    EXP[(LOG[a as usize] as usize + LOG[b as usize] as usize) % 255]
+}
+
+// Division in GF(256), b must not be zero.
+fn gf_div(a: u8, b: u8) -> u8 {
+    assert_ne!(b, 0);
+    if a == 0 {
+        return 0;
+    }
+    EXP[(LOG[a as usize] as usize + 255 - LOG[b as usize] as usize) % 255]
+}
+
+fn group_into_blocks(bv: &BitVec<Msb0, u8>, layout: &[usize]) -> Vec<Vec<u8>> {
     let data = bv.as_slice();
     assert_eq!(data.len(), layout.iter().sum());
 
@@ -129,6 +317,61 @@ static REMAINDER_BITS: [usize; 40] = [
     3, 3, 0, 0, 0, 0, 0, 0,
 ];
 
+// How many additional remainder bits needs to be added for each rMQR version.
+static RMQR_REMAINDER_BITS: [usize; 32] = [
+    0, 3, 5, 6, 1, 2, 3, 1, 4, 5, 2, 1, 0, 2, 7, 6, 4, 1, 6, 4, 3, 0, 1, 4, 6, 7, 2, 1, 2, 0, 3, 4,
+];
+
+// Generator polynomials in alpha notation, indexed by the count of ec codewords.
+// The leading term is always alpha^0 and is left out.
+// Counts that are never used by any QR, Micro QR or rMQR version and ECLevel are empty.
+static GEN_POLYS: [&[u8]; 31] = [
+    &[],
+    &[],
+    &[25, 1],
+    &[],
+    &[],
+    &[113, 164, 166, 119, 10],
+    &[166, 0, 134, 5, 176, 15],
+    &[87, 229, 146, 149, 238, 102, 21],
+    &[175, 238, 208, 249, 215, 252, 196, 28],
+    &[95, 246, 137, 231, 235, 149, 11, 123, 36],
+    &[251, 67, 46, 61, 118, 70, 64, 94, 32, 45],
+    &[],
+    &[102, 43, 98, 121, 187, 113, 198, 143, 131, 87, 157, 66],
+    &[74, 152, 176, 100, 86, 100, 106, 104, 130, 218, 206, 140, 78],
+    &[199, 249, 155, 48, 190, 124, 218, 137, 216, 87, 207, 59, 22, 91],
+    &[8, 183, 61, 91, 202, 37, 51, 58, 58, 237, 140, 124, 5, 99, 105],
+    &[120, 104, 107, 109, 102, 161, 76, 3, 91, 191, 147, 169, 182, 194, 225, 120],
+    &[43, 139, 206, 78, 43, 239, 123, 206, 214, 147, 24, 99, 150, 39, 243, 163, 136],
+    &[215, 234, 158, 94, 184, 97, 118, 170, 79, 187, 152, 148, 252, 179, 5, 98, 96, 153],
+    &[],
+    &[
+        17, 60, 79, 50, 61, 163, 26, 187, 202, 180, 221, 225, 83, 239, 156, 164, 212, 212, 188, 190,
+    ],
+    &[],
+    &[
+        210, 171, 247, 242, 93, 230, 14, 109, 221, 53, 200, 74, 8, 172, 98, 80, 219, 134, 160, 105, 165, 231,
+    ],
+    &[],
+    &[
+        229, 121, 135, 48, 211, 117, 251, 126, 159, 180, 169, 152, 192, 226, 228, 218, 111, 0, 117, 232, 87, 96, 227, 21,
+    ],
+    &[],
+    &[
+        173, 125, 158, 2, 103, 182, 118, 17, 145, 201, 111, 28, 165, 53, 161, 21, 245, 142, 13, 102, 48, 227, 153, 145, 218, 70,
+    ],
+    &[],
+    &[
+        168, 223, 200, 104, 224, 234, 108, 180, 110, 190, 195, 147, 205, 27, 232, 201, 21, 43, 245, 87, 42, 195, 212, 119, 242, 37, 9, 123,
+    ],
+    &[],
+    &[
+        41, 173, 145, 152, 216, 31, 179, 182, 50, 48, 110, 86, 239, 96, 222, 125, 42, 173, 226, 193, 224, 130, 156, 37, 251, 216, 238, 40,
+        192, 180,
+    ],
+];
+
 // Encode 2^x in GF(256) arithmetic.
 static EXP: [u8; 256] = [
     1, 2, 4, 8, 16, 32, 64, 128, 29, 58, 116, 232, 205, 135, 19, 38, 76, 152, 45, 90, 180, 117,
@@ -1,26 +1,30 @@
 //! Contains various QR specific encoding info.
 
 use crate::data;
+use crate::ec::ECLevel;
 use crate::mask::Mask;
+use crate::micro::MicroVersion;
+use crate::rmqr::RmqrVersion;
+use crate::version::Version;
 
 use bitvec::prelude::*;
 
 /// Returns the total codewords for a given version end error correction.
-pub fn total_codewords() -> usize {
-    let d = block_data();
+pub fn total_codewords(v: Version, ecl: ECLevel) -> usize {
+    let d = block_data(v, ecl);
     d.1 * d.2 + d.3 * d.4
 }
 
 /// Returns the total data bits possible for a given version and error correction.
-pub fn total_bits() -> usize {
-    8 * total_codewords()
+pub fn total_bits(v: Version, ecl: ECLevel) -> usize {
+    8 * total_codewords(v, ecl)
 }
 
 /// Returns a vector of codewords counts per block.
 /// The length specifies how many blocks there are and each element
 /// how many codewords exist in that block.
-pub fn group_block_count() -> Vec<usize> {
-    let data = block_data();
+pub fn group_block_count(v: Version, ecl: ECLevel) -> Vec<usize> {
+    let data = block_data(v, ecl);
     let mut v = Vec::new();
     v.extend((0..data.1).map(|_| data.2));
     v.extend((0..data.3).map(|_| data.4));
@@ -28,30 +32,497 @@ pub fn group_block_count() -> Vec<usize> {
 }
 
 /// Returns error correction codewords per block.
-pub fn block_ec_count() -> usize {
-    block_data().0
+pub fn block_ec_count(v: Version, ecl: ECLevel) -> usize {
+    block_data(v, ecl).0
 }
 
 /// Returns the format BitVec representation to be embedded.
-pub fn format_info(mask: Mask) -> BitVec<Lsb0, u8> {
-    let x = FORMAT_INFO[mask.0];
-    let mut bv = BitVec::<Lsb0, u8>::with_capacity(15);
+pub fn format_info(ecl: ECLevel, mask: Mask) -> BitVec<Msb0, u8> {
+    let x = FORMAT_INFO[ecl as usize][mask.0];
+    let mut bv = BitVec::<Msb0, u8>::with_capacity(15);
     data::append(&mut bv, x as u32, 15);
     bv
 }
 
-fn block_data() -> (usize, usize, usize, usize, usize) {
-    (15, 1, 55, 0, 0)
+/// Returns the version BitVec representation to be embedded.
+/// Only versions 7 and up contains version information.
+pub fn version_info(v: Version) -> Option<BitVec<Msb0, u8>> {
+    if v.extra_version_areas() {
+        let x = VERSION_INFO[v.0 - 7];
+        let mut bv = BitVec::<Msb0, u8>::with_capacity(18);
+        data::append(&mut bv, x, 18);
+        Some(bv)
+    } else {
+        None
+    }
 }
 
-// Format information for mask x ECLevel.
-static FORMAT_INFO: [u16; 8] = [
-    0b111011111000100,
-    0b111001011110011,
-    0b111110110101010,
-    0b111100010011101,
-    0b110011000101111,
-    0b110001100011000,
-    0b110110001000001,
-    0b110100101110110,
+/// Returns the error correction and mask of format info read from a QR code.
+/// Up to 3 bit errors are corrected, None if there are more.
+pub fn decode_format_info(bits: u16) -> Option<(ECLevel, Mask)> {
+    let ecls = [ECLevel::L, ECLevel::M, ECLevel::Q, ECLevel::H];
+    let (i, distance) = FORMAT_INFO
+        .iter()
+        .flatten()
+        .map(|x| (x ^ bits).count_ones())
+        .enumerate()
+        .min_by_key(|x| x.1)
+        .unwrap();
+    if distance > 3 {
+        return None;
+    }
+    Some((ecls[i / 8], Mask::new(i % 8)))
+}
+
+/// Returns the version of version info read from a QR code, version 7 and up.
+/// Up to 3 bit errors are corrected, None if there are more.
+pub fn decode_version_info(bits: u32) -> Option<Version> {
+    let (i, distance) = VERSION_INFO
+        .iter()
+        .map(|x| (x ^ bits).count_ones())
+        .enumerate()
+        .min_by_key(|x| x.1)
+        .unwrap();
+    if distance > 3 {
+        return None;
+    }
+    Some(Version::new(i + 7))
+}
+
+/// Returns the data bits for a Micro QR version and error correction,
+/// None if the version doesn't support the error correction level.
+/// M1 and M3 ends with a 4 bit codeword, so it's not always a whole number of bytes.
+pub fn micro_data_bits(v: MicroVersion, ecl: ECLevel) -> Option<usize> {
+    micro_symbol(v, ecl).map(|x| MICRO_SYMBOLS[x].2)
+}
+
+/// Returns the error correction codewords for a Micro QR version and error correction,
+/// None if the version doesn't support the error correction level.
+pub fn micro_ec_count(v: MicroVersion, ecl: ECLevel) -> Option<usize> {
+    micro_symbol(v, ecl).map(|x| MICRO_SYMBOLS[x].3)
+}
+
+/// Returns the Micro QR format BitVec representation to be embedded.
+/// The mask must be one of the masks Micro QR codes use.
+pub fn micro_format_info(v: MicroVersion, ecl: ECLevel, mask: Mask) -> Option<BitVec<Msb0, u8>> {
+    let symbol = micro_symbol(v, ecl)?;
+    let x = MICRO_FORMAT_INFO[symbol << 2 | mask.micro_pattern()?];
+    let mut bv = BitVec::<Msb0, u8>::with_capacity(15);
+    data::append(&mut bv, x as u32, 15);
+    Some(bv)
+}
+
+// The symbol number of a Micro QR version and error correction.
+fn micro_symbol(v: MicroVersion, ecl: ECLevel) -> Option<usize> {
+    MICRO_SYMBOLS.iter().position(|x| x.0 == v.0 && x.1 == ecl)
+}
+
+/// Returns the total codewords for an rMQR version and error correction,
+/// None if the error correction level isn't M or H.
+pub fn rmqr_total_codewords(v: RmqrVersion, ecl: ECLevel) -> Option<usize> {
+    let d = rmqr_block_data(v, ecl)?;
+    Some(d.1 * d.2 + d.3 * d.4)
+}
+
+/// Returns the codeword count for each block of an rMQR version and error correction.
+pub fn rmqr_group_block_count(v: RmqrVersion, ecl: ECLevel) -> Option<Vec<usize>> {
+    let data = rmqr_block_data(v, ecl)?;
+    let mut v = Vec::new();
+    v.extend((0..data.1).map(|_| data.2));
+    v.extend((0..data.3).map(|_| data.4));
+    Some(v)
+}
+
+/// Returns the error correction codewords per block of an rMQR version and error correction.
+pub fn rmqr_block_ec_count(v: RmqrVersion, ecl: ECLevel) -> Option<usize> {
+    rmqr_block_data(v, ecl).map(|x| x.0)
+}
+
+/// Returns the rMQR format BitVec representations to be embedded,
+/// next to the finder and next to the sub-finder.
+/// They are masked differently.
+pub fn rmqr_format_info(v: RmqrVersion, ecl: ECLevel) -> Option<(BitVec<Msb0, u8>, BitVec<Msb0, u8>)> {
+    let ecl_bit = match ecl {
+        ECLevel::M => 0,
+        ECLevel::H => 1,
+        _ => return None,
+    };
+    let x = RMQR_FORMAT_INFO[ecl_bit][v.0];
+    let mut finder = BitVec::<Msb0, u8>::with_capacity(18);
+    data::append(&mut finder, x ^ 0b011111101010110010, 18);
+    let mut sub_finder = BitVec::<Msb0, u8>::with_capacity(18);
+    data::append(&mut sub_finder, x ^ 0b100000101001111011, 18);
+    Some((finder, sub_finder))
+}
+
+fn rmqr_block_data(v: RmqrVersion, ecl: ECLevel) -> Option<(usize, usize, usize, usize, usize)> {
+    match ecl {
+        ECLevel::M => Some(RMQR_BLOCK_INFO[v.0 * 2]),
+        ECLevel::H => Some(RMQR_BLOCK_INFO[v.0 * 2 + 1]),
+        _ => None,
+    }
+}
+
+fn block_data(v: Version, ecl: ECLevel) -> (usize, usize, usize, usize, usize) {
+    BLOCK_INFO[v.index() * 4 + ecl as usize]
+}
+
+// Format information for ECLevel x mask.
+static FORMAT_INFO: [[u16; 8]; 4] = [
+    // ECLevel::L
+    [
+        0b111011111000100,
+        0b111001011110011,
+        0b111110110101010,
+        0b111100010011101,
+        0b110011000101111,
+        0b110001100011000,
+        0b110110001000001,
+        0b110100101110110,
+    ],
+    // ECLevel::M
+    [
+        0b101010000010010,
+        0b101000100100101,
+        0b101111001111100,
+        0b101101101001011,
+        0b100010111111001,
+        0b100000011001110,
+        0b100111110010111,
+        0b100101010100000,
+    ],
+    // ECLevel::Q
+    [
+        0b011010101011111,
+        0b011000001101000,
+        0b011111100110001,
+        0b011101000000110,
+        0b010010010110100,
+        0b010000110000011,
+        0b010111011011010,
+        0b010101111101101,
+    ],
+    // ECLevel::H
+    [
+        0b001011010001001,
+        0b001001110111110,
+        0b001110011100111,
+        0b001100111010000,
+        0b000011101100010,
+        0b000001001010101,
+        0b000110100001100,
+        0b000100000111011,
+    ],
+];
+
+// Version information for versions 7 and up.
+static VERSION_INFO: [u32; 34] = [
+    0b000111110010010100,
+    0b001000010110111100,
+    0b001001101010011001,
+    0b001010010011010011,
+    0b001011101111110110,
+    0b001100011101100010,
+    0b001101100001000111,
+    0b001110011000001101,
+    0b001111100100101000,
+    0b010000101101111000,
+    0b010001010001011101,
+    0b010010101000010111,
+    0b010011010100110010,
+    0b010100100110100110,
+    0b010101011010000011,
+    0b010110100011001001,
+    0b010111011111101100,
+    0b011000111011000100,
+    0b011001000111100001,
+    0b011010111110101011,
+    0b011011000010001110,
+    0b011100110000011010,
+    0b011101001100111111,
+    0b011110110101110101,
+    0b011111001001010000,
+    0b100000100111010101,
+    0b100001011011110000,
+    0b100010100010111010,
+    0b100011011110011111,
+    0b100100101100001011,
+    0b100101010000101110,
+    0b100110101001100100,
+    0b100111010101000001,
+    0b101000110001101001,
+];
+
+// Error correction blocks for each version and ECLevel, ordered L, M, Q, H.
+// (ec codewords per block, blocks in group 1, data codewords in group 1 blocks,
+// blocks in group 2, data codewords in group 2 blocks)
+static BLOCK_INFO: [(usize, usize, usize, usize, usize); 160] = [
+    (7, 1, 19, 0, 0),      // 1-L
+    (10, 1, 16, 0, 0),     // 1-M
+    (13, 1, 13, 0, 0),     // 1-Q
+    (17, 1, 9, 0, 0),      // 1-H
+    (10, 1, 34, 0, 0),     // 2-L
+    (16, 1, 28, 0, 0),     // 2-M
+    (22, 1, 22, 0, 0),     // 2-Q
+    (28, 1, 16, 0, 0),     // 2-H
+    (15, 1, 55, 0, 0),     // 3-L
+    (26, 1, 44, 0, 0),     // 3-M
+    (18, 2, 17, 0, 0),     // 3-Q
+    (22, 2, 13, 0, 0),     // 3-H
+    (20, 1, 80, 0, 0),     // 4-L
+    (18, 2, 32, 0, 0),     // 4-M
+    (26, 2, 24, 0, 0),     // 4-Q
+    (16, 4, 9, 0, 0),      // 4-H
+    (26, 1, 108, 0, 0),    // 5-L
+    (24, 2, 43, 0, 0),     // 5-M
+    (18, 2, 15, 2, 16),    // 5-Q
+    (22, 2, 11, 2, 12),    // 5-H
+    (18, 2, 68, 0, 0),     // 6-L
+    (16, 4, 27, 0, 0),     // 6-M
+    (24, 4, 19, 0, 0),     // 6-Q
+    (28, 4, 15, 0, 0),     // 6-H
+    (20, 2, 78, 0, 0),     // 7-L
+    (18, 4, 31, 0, 0),     // 7-M
+    (18, 2, 14, 4, 15),    // 7-Q
+    (26, 4, 13, 1, 14),    // 7-H
+    (24, 2, 97, 0, 0),     // 8-L
+    (22, 2, 38, 2, 39),    // 8-M
+    (22, 4, 18, 2, 19),    // 8-Q
+    (26, 4, 14, 2, 15),    // 8-H
+    (30, 2, 116, 0, 0),    // 9-L
+    (22, 3, 36, 2, 37),    // 9-M
+    (20, 4, 16, 4, 17),    // 9-Q
+    (24, 4, 12, 4, 13),    // 9-H
+    (18, 2, 68, 2, 69),    // 10-L
+    (26, 4, 43, 1, 44),    // 10-M
+    (24, 6, 19, 2, 20),    // 10-Q
+    (28, 6, 15, 2, 16),    // 10-H
+    (20, 4, 81, 0, 0),     // 11-L
+    (30, 1, 50, 4, 51),    // 11-M
+    (28, 4, 22, 4, 23),    // 11-Q
+    (24, 3, 12, 8, 13),    // 11-H
+    (24, 2, 92, 2, 93),    // 12-L
+    (22, 6, 36, 2, 37),    // 12-M
+    (26, 4, 20, 6, 21),    // 12-Q
+    (28, 7, 14, 4, 15),    // 12-H
+    (26, 4, 107, 0, 0),    // 13-L
+    (22, 8, 37, 1, 38),    // 13-M
+    (24, 8, 20, 4, 21),    // 13-Q
+    (22, 12, 11, 4, 12),   // 13-H
+    (30, 3, 115, 1, 116),  // 14-L
+    (24, 4, 40, 5, 41),    // 14-M
+    (20, 11, 16, 5, 17),   // 14-Q
+    (24, 11, 12, 5, 13),   // 14-H
+    (22, 5, 87, 1, 88),    // 15-L
+    (24, 5, 41, 5, 42),    // 15-M
+    (30, 5, 24, 7, 25),    // 15-Q
+    (24, 11, 12, 7, 13),   // 15-H
+    (24, 5, 98, 1, 99),    // 16-L
+    (28, 7, 45, 3, 46),    // 16-M
+    (24, 15, 19, 2, 20),   // 16-Q
+    (30, 3, 15, 13, 16),   // 16-H
+    (28, 1, 107, 5, 108),  // 17-L
+    (28, 10, 46, 1, 47),   // 17-M
+    (28, 1, 22, 15, 23),   // 17-Q
+    (28, 2, 14, 17, 15),   // 17-H
+    (30, 5, 120, 1, 121),  // 18-L
+    (26, 9, 43, 4, 44),    // 18-M
+    (28, 17, 22, 1, 23),   // 18-Q
+    (28, 2, 14, 19, 15),   // 18-H
+    (28, 3, 113, 4, 114),  // 19-L
+    (26, 3, 44, 11, 45),   // 19-M
+    (26, 17, 21, 4, 22),   // 19-Q
+    (26, 9, 13, 16, 14),   // 19-H
+    (28, 3, 107, 5, 108),  // 20-L
+    (26, 3, 41, 13, 42),   // 20-M
+    (30, 15, 24, 5, 25),   // 20-Q
+    (28, 15, 15, 10, 16),  // 20-H
+    (28, 4, 116, 4, 117),  // 21-L
+    (26, 17, 42, 0, 0),    // 21-M
+    (28, 17, 22, 6, 23),   // 21-Q
+    (30, 19, 16, 6, 17),   // 21-H
+    (28, 2, 111, 7, 112),  // 22-L
+    (28, 17, 46, 0, 0),    // 22-M
+    (30, 7, 24, 16, 25),   // 22-Q
+    (24, 34, 13, 0, 0),    // 22-H
+    (30, 4, 121, 5, 122),  // 23-L
+    (28, 4, 47, 14, 48),   // 23-M
+    (30, 11, 24, 14, 25),  // 23-Q
+    (30, 16, 15, 14, 16),  // 23-H
+    (30, 6, 117, 4, 118),  // 24-L
+    (28, 6, 45, 14, 46),   // 24-M
+    (30, 11, 24, 16, 25),  // 24-Q
+    (30, 30, 16, 2, 17),   // 24-H
+    (26, 8, 106, 4, 107),  // 25-L
+    (28, 8, 47, 13, 48),   // 25-M
+    (30, 7, 24, 22, 25),   // 25-Q
+    (30, 22, 15, 13, 16),  // 25-H
+    (28, 10, 114, 2, 115), // 26-L
+    (28, 19, 46, 4, 47),   // 26-M
+    (28, 28, 22, 6, 23),   // 26-Q
+    (30, 33, 16, 4, 17),   // 26-H
+    (30, 8, 122, 4, 123),  // 27-L
+    (28, 22, 45, 3, 46),   // 27-M
+    (30, 8, 23, 26, 24),   // 27-Q
+    (30, 12, 15, 28, 16),  // 27-H
+    (30, 3, 117, 10, 118), // 28-L
+    (28, 3, 45, 23, 46),   // 28-M
+    (30, 4, 24, 31, 25),   // 28-Q
+    (30, 11, 15, 31, 16),  // 28-H
+    (30, 7, 116, 7, 117),  // 29-L
+    (28, 21, 45, 7, 46),   // 29-M
+    (30, 1, 23, 37, 24),   // 29-Q
+    (30, 19, 15, 26, 16),  // 29-H
+    (30, 5, 115, 10, 116), // 30-L
+    (28, 19, 47, 10, 48),  // 30-M
+    (30, 15, 24, 25, 25),  // 30-Q
+    (30, 23, 15, 25, 16),  // 30-H
+    (30, 13, 115, 3, 116), // 31-L
+    (28, 2, 46, 29, 47),   // 31-M
+    (30, 42, 24, 1, 25),   // 31-Q
+    (30, 23, 15, 28, 16),  // 31-H
+    (30, 17, 115, 0, 0),   // 32-L
+    (28, 10, 46, 23, 47),  // 32-M
+    (30, 10, 24, 35, 25),  // 32-Q
+    (30, 19, 15, 35, 16),  // 32-H
+    (30, 17, 115, 1, 116), // 33-L
+    (28, 14, 46, 21, 47),  // 33-M
+    (30, 29, 24, 19, 25),  // 33-Q
+    (30, 11, 15, 46, 16),  // 33-H
+    (30, 13, 115, 6, 116), // 34-L
+    (28, 14, 46, 23, 47),  // 34-M
+    (30, 44, 24, 7, 25),   // 34-Q
+    (30, 59, 16, 1, 17),   // 34-H
+    (30, 12, 121, 7, 122), // 35-L
+    (28, 12, 47, 26, 48),  // 35-M
+    (30, 39, 24, 14, 25),  // 35-Q
+    (30, 22, 15, 41, 16),  // 35-H
+    (30, 6, 121, 14, 122), // 36-L
+    (28, 6, 47, 34, 48),   // 36-M
+    (30, 46, 24, 10, 25),  // 36-Q
+    (30, 2, 15, 64, 16),   // 36-H
+    (30, 17, 122, 4, 123), // 37-L
+    (28, 29, 46, 14, 47),  // 37-M
+    (30, 49, 24, 10, 25),  // 37-Q
+    (30, 24, 15, 46, 16),  // 37-H
+    (30, 4, 122, 18, 123), // 38-L
+    (28, 13, 46, 32, 47),  // 38-M
+    (30, 48, 24, 14, 25),  // 38-Q
+    (30, 42, 15, 32, 16),  // 38-H
+    (30, 20, 117, 4, 118), // 39-L
+    (28, 40, 47, 7, 48),   // 39-M
+    (30, 43, 24, 22, 25),  // 39-Q
+    (30, 10, 15, 67, 16),  // 39-H
+    (30, 19, 118, 6, 119), // 40-L
+    (28, 18, 47, 31, 48),  // 40-M
+    (30, 34, 24, 34, 25),  // 40-Q
+    (30, 20, 15, 61, 16),  // 40-H
+];
+
+// Micro QR symbols in symbol number order.
+// Contains (version, ECLevel, data bits, ec codewords).
+// M1 only has error detection, it uses ECLevel::L here.
+static MICRO_SYMBOLS: [(usize, ECLevel, usize, usize); 8] = [
+    (1, ECLevel::L, 20, 2),   // M1
+    (2, ECLevel::L, 40, 5),   // M2-L
+    (2, ECLevel::M, 32, 6),   // M2-M
+    (3, ECLevel::L, 84, 6),   // M3-L
+    (3, ECLevel::M, 68, 8),   // M3-M
+    (4, ECLevel::L, 128, 8),  // M4-L
+    (4, ECLevel::M, 112, 10), // M4-M
+    (4, ECLevel::Q, 80, 14),  // M4-Q
+];
+
+// Micro QR format information for symbol number x mask pattern.
+// Already BCH encoded and masked with 0b100010001000101.
+static MICRO_FORMAT_INFO: [u16; 32] = [
+    0x4445, 0x4172, 0x4E2B, 0x4B1C, 0x55AE, 0x5099, 0x5FC0, 0x5AF7, // M1, M2-L
+    0x6793, 0x62A4, 0x6DFD, 0x68CA, 0x7678, 0x734F, 0x7C16, 0x7921, // M2-M, M3-L
+    0x06DE, 0x03E9, 0x0CB0, 0x0987, 0x1735, 0x1202, 0x1D5B, 0x186C, // M3-M, M4-L
+    0x2508, 0x203F, 0x2F66, 0x2A51, 0x34E3, 0x31D4, 0x3E8D, 0x3BBA, // M4-M, M4-Q
+];
+
+// rMQR error correction information, for each version and ECLevel M and H.
+// Has the same layout as BLOCK_INFO.
+static RMQR_BLOCK_INFO: [(usize, usize, usize, usize, usize); 64] = [
+    (7, 1, 6, 0, 0),    // R7x43-M
+    (10, 1, 3, 0, 0),   // R7x43-H
+    (9, 1, 12, 0, 0),   // R7x59-M
+    (14, 1, 7, 0, 0),   // R7x59-H
+    (12, 1, 20, 0, 0),  // R7x77-M
+    (22, 1, 10, 0, 0),  // R7x77-H
+    (16, 1, 28, 0, 0),  // R7x99-M
+    (30, 1, 14, 0, 0),  // R7x99-H
+    (24, 1, 44, 0, 0),  // R7x139-M
+    (22, 2, 12, 0, 0),  // R7x139-H
+    (9, 1, 12, 0, 0),   // R9x43-M
+    (14, 1, 7, 0, 0),   // R9x43-H
+    (12, 1, 21, 0, 0),  // R9x59-M
+    (22, 1, 11, 0, 0),  // R9x59-H
+    (18, 1, 31, 0, 0),  // R9x77-M
+    (16, 1, 8, 1, 9),   // R9x77-H
+    (24, 1, 42, 0, 0),  // R9x99-M
+    (22, 2, 11, 0, 0),  // R9x99-H
+    (18, 1, 31, 1, 32), // R9x139-M
+    (22, 3, 11, 0, 0),  // R9x139-H
+    (8, 1, 7, 0, 0),    // R11x27-M
+    (10, 1, 5, 0, 0),   // R11x27-H
+    (12, 1, 19, 0, 0),  // R11x43-M
+    (20, 1, 11, 0, 0),  // R11x43-H
+    (16, 1, 31, 0, 0),  // R11x59-M
+    (16, 1, 7, 1, 8),   // R11x59-H
+    (24, 1, 43, 0, 0),  // R11x77-M
+    (22, 1, 11, 1, 12), // R11x77-H
+    (16, 1, 28, 1, 29), // R11x99-M
+    (30, 1, 14, 1, 15), // R11x99-H
+    (24, 2, 42, 0, 0),  // R11x139-M
+    (30, 3, 14, 0, 0),  // R11x139-H
+    (9, 1, 12, 0, 0),   // R13x27-M
+    (14, 1, 7, 0, 0),   // R13x27-H
+    (14, 1, 27, 0, 0),  // R13x43-M
+    (28, 1, 13, 0, 0),  // R13x43-H
+    (22, 1, 38, 0, 0),  // R13x59-M
+    (20, 2, 10, 0, 0),  // R13x59-H
+    (16, 1, 26, 1, 27), // R13x77-M
+    (28, 1, 14, 1, 15), // R13x77-H
+    (20, 1, 36, 1, 37), // R13x99-M
+    (26, 1, 11, 2, 12), // R13x99-H
+    (20, 2, 35, 1, 36), // R13x139-M
+    (28, 2, 13, 2, 14), // R13x139-H
+    (18, 1, 33, 0, 0),  // R15x43-M
+    (18, 1, 7, 1, 8),   // R15x43-H
+    (26, 1, 48, 0, 0),  // R15x59-M
+    (24, 2, 13, 0, 0),  // R15x59-H
+    (18, 1, 33, 1, 34), // R15x77-M
+    (24, 2, 10, 1, 11), // R15x77-H
+    (24, 2, 44, 0, 0),  // R15x99-M
+    (22, 4, 12, 0, 0),  // R15x99-H
+    (24, 2, 42, 1, 43), // R15x139-M
+    (26, 1, 13, 4, 14), // R15x139-H
+    (22, 1, 39, 0, 0),  // R17x43-M
+    (20, 1, 10, 1, 11), // R17x43-H
+    (16, 2, 28, 0, 0),  // R17x59-M
+    (30, 2, 14, 0, 0),  // R17x59-H
+    (22, 2, 39, 0, 0),  // R17x77-M
+    (28, 1, 12, 2, 13), // R17x77-H
+    (20, 2, 33, 1, 34), // R17x99-M
+    (26, 4, 14, 0, 0),  // R17x99-H
+    (20, 4, 38, 0, 0),  // R17x139-M
+    (26, 2, 12, 4, 13), // R17x139-H
+];
+
+// rMQR format information for ECLevel x version, BCH encoded, but not yet masked.
+static RMQR_FORMAT_INFO: [[u32; 32]; 2] = [
+    // ECLevel::M
+    [
+        0x00000, 0x01F25, 0x0216F, 0x03E4A, 0x042DE, 0x05DFB, 0x063B1, 0x07C94, 0x085BC, 0x09A99, 0x0A4D3, 0x0BBF6, 0x0C762, 0x0D847,
+        0x0E60D, 0x0F928, 0x10B78, 0x1145D, 0x12A17, 0x13532, 0x149A6, 0x15683, 0x168C9, 0x177EC, 0x18EC4, 0x191E1, 0x1AFAB, 0x1B08E,
+        0x1CC1A, 0x1D33F, 0x1ED75, 0x1F250,
+    ],
+    // ECLevel::H
+    [
+        0x209D5, 0x216F0, 0x228BA, 0x2379F, 0x24B0B, 0x2542E, 0x26A64, 0x27541, 0x28C69, 0x2934C, 0x2AD06, 0x2B223, 0x2CEB7, 0x2D192,
+        0x2EFD8, 0x2F0FD, 0x302AD, 0x31D88, 0x323C2, 0x33CE7, 0x34073, 0x35F56, 0x3611C, 0x37E39, 0x38711, 0x39834, 0x3A67E, 0x3B95B,
+        0x3C5CF, 0x3DAEA, 0x3E4A0, 0x3FB85,
+    ],
 ];
@@ -63,6 +63,9 @@ pub use builder::*;
 pub mod data;
 pub use data::*;
 
+pub mod decode;
+pub use decode::{DecodeError, Decoded};
+
 pub mod ec;
 pub use ec::*;
 
@@ -75,23 +78,50 @@ pub use mask::*;
 pub mod matrix;
 pub use matrix::{Matrix, Module};
 
+pub mod micro;
+pub use micro::{MicroQr, MicroQrBuilder, MicroVersion};
+
 pub mod mode;
 pub use mode::Mode;
 
 pub mod qr;
 pub use qr::Qr;
 
+pub mod renderansi;
+pub use renderansi::*;
+
 pub mod rendercommons;
 pub use rendercommons::*;
 
+pub mod renderpng;
+pub use renderpng::*;
+
 pub mod rendersvg;
 pub use rendersvg::*;
 
 pub mod renderstring;
 pub use renderstring::*;
 
+pub mod renderunicode;
+pub use renderunicode::*;
+
 pub mod renderfordodrio;
 pub use renderfordodrio::*;
 
+pub mod rmqr;
+pub use rmqr::{Rmqr, RmqrBuilder, RmqrVersion};
+
+pub mod scan;
+
+pub mod segment;
+pub use segment::Segment;
+
+pub mod sequence;
+pub use sequence::{QrSequence, StructuredAppend};
+
+mod sjis;
+
 pub mod version;
 pub use version::Version;
+
+mod zlib;
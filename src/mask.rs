@@ -18,6 +18,18 @@ impl Mask {
         Mask(v)
     }
 
+    /// Create a mask from a Micro QR mask pattern.
+    /// Micro QR codes use the masks 1, 4, 6 and 7, referred to as pattern 0 to 3.
+    /// Fails if larger than 3.
+    pub fn micro(pattern: usize) -> Mask {
+        MICRO_MASKS[pattern]
+    }
+
+    /// Returns the Micro QR mask pattern, None if Micro QR codes don't use the mask.
+    pub fn micro_pattern(&self) -> Option<usize> {
+        MICRO_MASKS.iter().position(|x| x == self)
+    }
+
     fn fun(&self) -> Box<dyn Fn(usize, usize) -> bool> {
         match self.0 {
             0 => Box::new(move |x, y| (x + y) % 2 == 0),
@@ -36,7 +48,7 @@ impl Mask {
 /// Evaluates masks.
 /// Returns the mask with the lowest score and a matrix with the mask applied.
 pub fn mask(matrix: &Matrix) -> (Mask, Matrix) {
-    let mut min_score = u16::max_value();
+    let mut min_score = u16::MAX;
     let mut res = None;
     for v in 0..8 {
         let mask = Mask::new(v);
@@ -50,6 +62,22 @@ pub fn mask(matrix: &Matrix) -> (Mask, Matrix) {
     res.unwrap()
 }
 
+/// Evaluates the masks Micro QR codes use.
+/// Returns the mask with the highest score and a matrix with the mask applied.
+pub fn mask_micro(matrix: &Matrix) -> (Mask, Matrix) {
+    let mut max_score = 0;
+    let mut res = None;
+    for mask in MICRO_MASKS.iter() {
+        let masked = apply_mask(*mask, matrix);
+        let score = evaluate_micro(&masked);
+        if res.is_none() || score > max_score {
+            max_score = score;
+            res = Some((*mask, masked));
+        }
+    }
+    res.unwrap()
+}
+
 /// Apply a mask of a specific type to a matrix.
 pub fn apply_mask(mask: Mask, matrix: &Matrix) -> Matrix {
     apply_mask_fun(mask.fun(), matrix)
@@ -66,10 +94,21 @@ pub fn evaluate(matrix: &Matrix) -> u16 {
     e1 + e2 + e3 + e4
 }
 
+/// Evaluate the mask score of a Micro QR matrix, higher is better.
+/// Dark modules along the right and bottom edges are counted,
+/// the edge with the fewest weighs 16 times as much.
+pub fn evaluate_micro(matrix: &Matrix) -> u16 {
+    let size = matrix.size();
+    // Skip the timing patterns.
+    let right = (1..size).filter(|y| matrix.is_dark(size - 1, *y)).count() as u16;
+    let bottom = (1..size).filter(|x| matrix.is_dark(*x, size - 1)).count() as u16;
+    cmp::min(right, bottom) * 16 + cmp::max(right, bottom)
+}
+
 // 5 in a row/col should give a score of 3, each extra gives a score of 1.
 fn evaluate_5_in_line(matrix: &Matrix) -> u16 {
     let mut res = 0;
-    for i in 0..matrix.size {
+    for i in 0..matrix.size() {
         res += eval_5_col(matrix, i);
         res += eval_5_row(matrix, i);
     }
@@ -80,7 +119,7 @@ fn eval_5_row(matrix: &Matrix, y: usize) -> u16 {
     let mut res = 0;
     let mut from = 0;
     let mut curr = matrix.is_dark(0, y);
-    for x in 1..matrix.size {
+    for x in 1..matrix.size() {
         if matrix.is_dark(x, y) == curr {
             res += diff_5(from, x)
         } else {
@@ -95,7 +134,7 @@ fn eval_5_col(matrix: &Matrix, x: usize) -> u16 {
     let mut res = 0;
     let mut from = 0;
     let mut curr = matrix.is_dark(x, 0);
-    for y in 1..matrix.size {
+    for y in 1..matrix.size() {
         if matrix.is_dark(x, y) == curr {
             res += diff_5(from, y)
         } else {
@@ -120,8 +159,8 @@ fn diff_5(from: usize, to: usize) -> u16 {
 // Each 2x2 square of the same color gives a score of 3.
 fn evaluate_2x2(matrix: &Matrix) -> u16 {
     let mut squares = 0;
-    for x in 0..matrix.size - 1 {
-        for y in 0..matrix.size - 1 {
+    for x in 0..matrix.size() - 1 {
+        for y in 0..matrix.size() - 1 {
             let square = [
                 matrix.is_dark(x, y),
                 matrix.is_dark(x + 1, y),
@@ -140,7 +179,7 @@ fn evaluate_2x2(matrix: &Matrix) -> u16 {
 // Each dark/light pattern found gives a score of 40.
 fn evaluate_dl_pattern(matrix: &Matrix) -> u16 {
     let mut count = 0;
-    for i in 0..matrix.size {
+    for i in 0..matrix.size() {
         count += count_dl_row(matrix, i);
         count += count_dl_col(matrix, i);
     }
@@ -148,16 +187,16 @@ fn evaluate_dl_pattern(matrix: &Matrix) -> u16 {
 }
 
 fn count_dl_row(matrix: &Matrix, y: usize) -> u16 {
-    let mut row = BitVec::<Lsb0, u8>::with_capacity(matrix.size);
-    for x in 0..matrix.size {
+    let mut row = BitVec::<Msb0, u8>::with_capacity(matrix.size());
+    for x in 0..matrix.size() {
         row.push(!matrix.is_dark(x, y));
     }
     count_dl_patterns(&row)
 }
 
 fn count_dl_col(matrix: &Matrix, x: usize) -> u16 {
-    let mut col = BitVec::<Lsb0, u8>::with_capacity(matrix.size);
-    for y in 0..matrix.size {
+    let mut col = BitVec::<Msb0, u8>::with_capacity(matrix.size());
+    for y in 0..matrix.size() {
         col.push(!matrix.is_dark(x, y));
     }
     count_dl_patterns(&col)
@@ -165,7 +204,7 @@ fn count_dl_col(matrix: &Matrix, x: usize) -> u16 {
 
 lazy_static! {
     // Dark/light patterns we should detect.
-    // <Lsb0 , u8> can't be initialized in lazy_static so we'll use a standard Vec.
+    // <Msb0 , u8> can't be initialized in lazy_static so we'll use a standard Vec.
     // Convert to bool once here to make later comparisons simpler.
     static ref DLP1: Vec<bool> = [0, 0, 0, 0, 1, 0, 1, 1, 1, 0, 1]
         .iter().map(|x| *x == 1).collect();
@@ -173,7 +212,7 @@ lazy_static! {
         .iter().map(|x| *x == 1).collect();
 }
 
-fn count_dl_patterns(bv: &BitVec<Lsb0, u8>) -> u16 {
+fn count_dl_patterns(bv: &BitVec<Msb0, u8>) -> u16 {
     let mut res = 0;
     // Each window is an iterator over 11 elements which we can
     // compare the patterns we search for against.
@@ -190,7 +229,7 @@ fn count_dl_patterns(bv: &BitVec<Lsb0, u8>) -> u16 {
 
 // Calculates a score depending on the light/dark ratio.
 fn evaluate_bw(matrix: &Matrix) -> u16 {
-    let total = matrix.size * matrix.size;
+    let total = matrix.modules.len();
     let dark = matrix.modules.iter().filter(|x| x.is_dark()).count();
     let ratio = ((dark as f32) / (total as f32) * 100.0) as i16;
     let low_5 = ratio - ratio % 5;
@@ -202,8 +241,8 @@ fn evaluate_bw(matrix: &Matrix) -> u16 {
 
 fn apply_mask_fun(f: Box<dyn Fn(usize, usize) -> bool>, matrix: &Matrix) -> Matrix {
     let mut res = matrix.clone();
-    for y in 0..res.size {
-        for x in 0..res.size {
+    for y in 0..res.height {
+        for x in 0..res.width {
             if matrix.is_data(x, y) && f(x, y) {
                 res.flip(x, y);
             }
@@ -211,3 +250,6 @@ fn apply_mask_fun(f: Box<dyn Fn(usize, usize) -> bool>, matrix: &Matrix) -> Matr
     }
     res
 }
+
+// The masks Micro QR codes use, indexed by the Micro QR mask pattern.
+static MICRO_MASKS: [Mask; 4] = [Mask(1), Mask(4), Mask(6), Mask(7)];
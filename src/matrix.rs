@@ -40,19 +40,12 @@ impl Module {
     /// Is the module a function module?
     /// This includes reserved modules as well.
     pub fn is_fun(&self) -> bool {
-        match self {
-            Module::Unknown => false,
-            Module::Data(_) => false,
-            _ => true,
-        }
+        !matches!(self, Module::Unknown | Module::Data(_))
     }
 
     /// Is the module a Data module?
     pub fn is_data(&self) -> bool {
-        match self {
-            Module::Data(_) => true,
-            _ => false,
-        }
+        matches!(self, Module::Data(_))
     }
 }
 
@@ -71,27 +64,43 @@ impl Not for Module {
 /// Matrix is a 2-dimensional grid holding the QR modules.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Matrix {
-    /// Size defines the width and height of the matrix.
-    pub size: usize,
+    /// Width of the matrix, in modules.
+    pub width: usize,
+
+    /// Height of the matrix, in modules.
+    pub height: usize,
 
     /// The modules.
     pub modules: Vec<Module>,
 }
 
 impl Matrix {
-    /// Create a new matrix, modules initialized to Unknown.
+    /// Create a new square matrix, modules initialized to Unknown.
     pub fn new(size: usize) -> Matrix {
+        Matrix::new_rect(size, size)
+    }
+
+    /// Create a new rectangular matrix, modules initialized to Unknown.
+    pub fn new_rect(width: usize, height: usize) -> Matrix {
         Matrix {
-            size: size,
-            modules: vec![Module::Unknown; size * size],
+            width,
+            height,
+            modules: vec![Module::Unknown; width * height],
         }
     }
 
+    /// Returns the size of a square matrix.
+    /// Fails if the matrix isn't square.
+    pub fn size(&self) -> usize {
+        assert_eq!(self.width, self.height);
+        self.width
+    }
+
     /// Map (x,y) coords to linear index.
     pub fn index(&self, x: usize, y: usize) -> usize {
-        assert!(x < self.size);
-        assert!(y < self.size);
-        self.size * y + x
+        assert!(x < self.width);
+        assert!(y < self.height);
+        self.width * y + x
     }
 
     /// Get module.
@@ -0,0 +1,314 @@
+//! Micro QR codes, M1 to M4.
+//!
+//! Smaller than a version 1 QR code, with a single finder pattern
+//! and a narrower quiet zone of 2 modules.
+//! Each Micro QR code holds a single segment,
+//! and the smaller versions only supports some modes.
+
+use crate::builder::{Error, ZigZagIt};
+use crate::data;
+use crate::ec::{self, ECLevel};
+use crate::info;
+use crate::mask::{self, Mask};
+use crate::matrix::{Matrix, Module};
+use crate::mode::Mode;
+use crate::rendercommons;
+
+use bitvec::prelude::*;
+
+/// Micro QR code version, M1 to M4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MicroVersion(pub usize);
+
+impl MicroVersion {
+    /// Create a new version, must be in the [1..4] range.
+    pub fn new(v: usize) -> MicroVersion {
+        assert!((1..=4).contains(&v));
+        MicroVersion(v)
+    }
+
+    /// Return the size of the Micro QR code.
+    pub fn size(&self) -> usize {
+        self.0 * 2 + 9
+    }
+
+    /// Returns the required len of the char count bit representation,
+    /// None if the version doesn't support the mode.
+    pub fn char_count_len(&self, mode: Mode) -> Option<usize> {
+        let lens = match mode {
+            Mode::Numeric => [Some(3), Some(4), Some(5), Some(6)],
+            Mode::Alphanumeric => [None, Some(3), Some(4), Some(5)],
+            Mode::Byte => [None, None, Some(4), Some(5)],
+            Mode::Kanji => [None, None, Some(3), Some(4)],
+        };
+        lens[self.0 - 1]
+    }
+
+    /// Returns the len of the terminator.
+    pub fn terminator_len(&self) -> usize {
+        self.0 * 2 + 1
+    }
+}
+
+/// The Micro QR code.
+///
+/// Encapsulates a matrix, the 2D-grid containing the modules
+/// and some information about the Micro QR code.
+/// Render the matrix with the render_matrix method of a renderer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MicroQr {
+    /// Version of the Micro QR code.
+    pub version: MicroVersion,
+
+    /// Error correction level.
+    /// M1 only has error detection, it's ECLevel::L.
+    pub ecl: ECLevel,
+
+    /// Encoding mode.
+    pub mode: Mode,
+
+    /// The modules.
+    pub matrix: Matrix,
+
+    /// The applied mask, one of the masks Micro QR codes use.
+    pub mask: Mask,
+}
+
+impl MicroQr {
+    /// Create a new Micro QR code from a string.
+    /// The smallest version that can hold the string is used.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let qr = MicroQr::new("12345").unwrap();
+    /// assert_eq!(qr.version, MicroVersion::new(1));
+    /// let svg = SvgRenderer::new().render_matrix(&qr.matrix);
+    /// ```
+    pub fn new(s: &str) -> Result<MicroQr, Error> {
+        MicroQrBuilder::new().ecl(ECLevel::L).into(s)
+    }
+
+    /// Returns the size of the Micro QR code.
+    pub fn size(&self) -> usize {
+        self.version.size()
+    }
+}
+
+/// Builder for a Micro QR code.
+pub struct MicroQrBuilder {
+    /// Version to use.
+    /// If not set a minimal version will be calculated.
+    pub version: Option<MicroVersion>,
+    /// Mask to use, must be one of the masks Micro QR codes use.
+    /// If not set the optimal mask will be chosen per the Micro QR specification.
+    pub mask: Option<Mask>,
+    /// Error correction level to use.
+    /// If not set ECLevel::L will be used. ECLevel::H isn't supported by any version.
+    pub ecl: ECLevel,
+    /// Encoding mode to use.
+    /// If not set it will be calculated from the input data.
+    pub mode: Option<Mode>,
+
+    /// Resulting matrix.
+    ///
+    /// Note that even though the matrix is not an Option it might still be invalid.
+    /// This to simplify the implementation.
+    pub matrix: Matrix,
+}
+
+impl Default for MicroQrBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MicroQrBuilder {
+    /// Create a new builder.
+    pub fn new() -> MicroQrBuilder {
+        MicroQrBuilder {
+            version: None,
+            mask: None,
+            ecl: ECLevel::L,
+            mode: None,
+
+            matrix: Matrix::new(0),
+        }
+    }
+
+    /// Set version. If not set the smallest applicable version will be used.
+    pub fn version(mut self, v: MicroVersion) -> Self {
+        // Override old tmp matrix.
+        self.matrix = Matrix::new(v.size());
+        self.version = Some(v);
+        self
+    }
+
+    /// Set mask to use, must be one of the masks Micro QR codes use.
+    /// If not set the best mask will be chosen according to the Micro QR spec.
+    pub fn mask(mut self, mask: Mask) -> Self {
+        assert!(mask.micro_pattern().is_some());
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Set error correction. Will default to ECLevel::L.
+    pub fn ecl(mut self, ecl: ECLevel) -> Self {
+        self.ecl = ecl;
+        self
+    }
+
+    /// Set the mode to use, will otherwise be calculated from input.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Build all elements and generate a Micro QR code.
+    pub fn into(mut self, s: &str) -> Result<MicroQr, Error> {
+        self.add_all(s)?;
+        self.into_micro_qr()
+    }
+
+    /// Convert the builder into a Micro QR code.
+    pub fn into_micro_qr(self) -> Result<MicroQr, Error> {
+        if !self.complete() {
+            return Err(Error::IncompleteBuilder);
+        }
+
+        Ok(MicroQr {
+            matrix: self.matrix,
+
+            version: self.version.unwrap(),
+            ecl: self.ecl,
+            mode: self.mode.unwrap(),
+            mask: self.mask.unwrap(),
+        })
+    }
+
+    /// Add all elements of a Micro QR code.
+    pub fn add_all(&mut self, s: &str) -> Result<(), Error> {
+        self.ensure_settings(s)?;
+        self.add_fun_patterns();
+        self.add_data(s)?;
+        self.mask_data();
+        self.add_format_info();
+
+        Ok(())
+    }
+
+    /// Add function patterns.
+    pub fn add_fun_patterns(&mut self) {
+        self.add_finder();
+        self.add_timing_patterns();
+        self.add_reserved_areas();
+    }
+
+    /// Add data.
+    pub fn add_data(&mut self, s: &str) -> Result<(), Error> {
+        self.ensure_settings(s)?;
+
+        let version = self.version.unwrap();
+        let ecl = self.ecl;
+
+        let v = data::encode_micro(s, self.mode.unwrap(), version, ecl)?;
+        let v = ec::add_micro(v, version, ecl)?;
+        self.add_raw_data(&v);
+
+        Ok(())
+    }
+
+    /// Add raw data.
+    pub fn add_raw_data(&mut self, v: &BitVec<Msb0, u8>) {
+        let mut vi = 0;
+        for (x, y) in ZigZagIt::new_micro(self.matrix.size()) {
+            if self.matrix.is_fun(x, y) {
+                continue;
+            }
+            self.matrix.set_data(x, y, v[vi]);
+            vi += 1;
+        }
+        assert_eq!(vi, v.len());
+    }
+
+    /// Mask data.
+    pub fn mask_data(&mut self) {
+        if let Some(mask) = self.mask {
+            self.matrix = mask::apply_mask(mask, &self.matrix);
+        } else {
+            let (mask, masked) = mask::mask_micro(&self.matrix);
+            self.mask = Some(mask);
+            self.matrix = masked;
+        }
+    }
+
+    /// Add format info.
+    pub fn add_format_info(&mut self) {
+        // Hard assumption that we have necessary data.
+        let format = info::micro_format_info(self.version.unwrap(), self.ecl, self.mask.unwrap()).unwrap();
+
+        // The most significant bit is placed first, below the finder.
+        // Then upwards to the right of the finder.
+        for (i, v) in format.iter().enumerate() {
+            if i < 8 {
+                self.matrix.set_fun(i + 1, 8, *v);
+            } else {
+                self.matrix.set_fun(8, 15 - i, *v);
+            }
+        }
+    }
+
+    /// Convert to debug string.
+    pub fn to_dbg_string(&self) -> String {
+        rendercommons::to_dbg_string(&self.matrix)
+    }
+
+    // Return true if the build is complete.
+    fn complete(&self) -> bool {
+        self.mode.is_some() && self.version.is_some() && self.mask.is_some() && self.matrix.complete()
+    }
+
+    // Ensure we have required settings, otherwise decide from the data.
+    fn ensure_settings(&mut self, s: &str) -> Result<(), Error> {
+        let mode = *self.mode.get_or_insert_with(|| Mode::from_str(s));
+        if self.version.is_none() {
+            // The smallest version that supports the mode and error correction and fits the data.
+            let mut res = Err(Error::UnsupportedMode);
+            for v in (1..=4).map(MicroVersion::new) {
+                res = data::encode_micro(s, mode, v, self.ecl).map(|_| v);
+                if res.is_ok() {
+                    break;
+                }
+            }
+            self.version = Some(res?);
+        }
+
+        // Ensure the matrix is initialized.
+        let size = self.version.unwrap().size();
+        if self.matrix.size() != size {
+            self.matrix = Matrix::new(size);
+        }
+        Ok(())
+    }
+
+    fn add_finder(&mut self) {
+        self.matrix.set_square(0, 0, 7, Module::Function(true));
+        self.matrix.set_square_outline(1, 1, 5, Module::Function(false));
+        // Separator to the right and below.
+        self.matrix.set_rect(7, 0, 7, 7, Module::Function(false));
+        self.matrix.set_rect(0, 7, 7, 7, Module::Function(false));
+    }
+
+    // The timing patterns are along the top and left edges.
+    fn add_timing_patterns(&mut self) {
+        for i in 8..self.matrix.size() {
+            let v = i % 2 == 0;
+            self.matrix.set(i, 0, Module::Function(v));
+            self.matrix.set(0, i, Module::Function(v));
+        }
+    }
+
+    fn add_reserved_areas(&mut self) {
+        // Below and to the right of the finder.
+        self.matrix.set_rect(1, 8, 8, 8, Module::Reserved);
+        self.matrix.set_rect(8, 1, 8, 7, Module::Reserved);
+    }
+}
@@ -1,18 +1,43 @@
 //! Encoding modes for a QR code.
 
+use crate::micro::MicroVersion;
+use crate::sjis;
+
 use bitvec::prelude::*;
 
 /// Encoding modes.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Mode {
+    /// Numeric mode supports the digits 0 to 9.
+    Numeric,
+    /// Alphanumeric mode supports 0-9, A-Z (uppercase only), space and $%*+-./:
+    Alphanumeric,
+    /// Kanji mode supports the double byte Shift JIS chars of JIS X 0208.
+    Kanji,
     /// Byte mode supports the ISO-8859-1 character set.
+    /// Other chars are encoded as UTF-8, preferably with an ECI designator.
     Byte,
 }
 
 impl Mode {
     /// Create Mode from string, decide from content.
+    /// The most compact mode is preferred. Latin-1 chars take a byte each,
+    /// so kanji mode is only used when byte mode needs UTF-8.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// assert_eq!(Mode::from_str("漢字"), Mode::Kanji);
+    /// // Also in JIS X 0208, but shorter in byte mode.
+    /// assert_eq!(Mode::from_str("±5°"), Mode::Byte);
+    /// ```
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Mode {
-        if Mode::in_byte(s) {
+        if Mode::in_numeric(s) {
+            Mode::Numeric
+        } else if Mode::in_alphanumeric(s) {
+            Mode::Alphanumeric
+        } else if !Mode::in_latin1(s) && Mode::in_kanji(s) {
+            Mode::Kanji
+        } else if Mode::in_byte(s) {
             Mode::Byte
         } else {
             // Should never happen.
@@ -23,19 +48,93 @@ impl Mode {
     /// Is this a valid mode for a string?
     pub fn matches(&self, s: &str) -> bool {
         match self {
+            Mode::Numeric => Mode::in_numeric(s),
+            Mode::Alphanumeric => Mode::in_alphanumeric(s),
+            Mode::Kanji => Mode::in_kanji(s),
             Mode::Byte => Mode::in_byte(s),
         }
     }
 
-    /// BitVec<Lsb0 , u8> representation.
-    pub fn to_bitvec(&self) -> BitVec<Lsb0, u8> {
+    /// BitVec<Msb0 , u8> representation.
+    pub fn to_bitvec(&self) -> BitVec<Msb0, u8> {
+        match self {
+            Mode::Numeric => bitvec![Msb0, u8;0, 0, 0, 1],
+            Mode::Alphanumeric => bitvec![Msb0, u8;0, 0, 1, 0],
+            Mode::Kanji => bitvec![Msb0, u8;1, 0, 0, 0],
+            Mode::Byte => bitvec![Msb0, u8;0, 1, 0, 0],
+        }
+    }
+
+    /// BitVec<Msb0 , u8> representation in a Micro QR code.
+    /// The len depends on the version, M1 only supports numeric mode and has no mode indicator.
+    pub fn to_micro_bitvec(&self, v: MicroVersion) -> BitVec<Msb0, u8> {
+        let x = match self {
+            Mode::Numeric => 0,
+            Mode::Alphanumeric => 1,
+            Mode::Byte => 2,
+            Mode::Kanji => 3,
+        };
+        let mut bv = BitVec::<Msb0, u8>::new();
+        bv.extend((0..v.0 - 1).rev().map(|i| (x >> i) & 1 != 0));
+        bv
+    }
+
+    /// BitVec<Msb0 , u8> representation in an rMQR code.
+    pub fn to_rmqr_bitvec(&self) -> BitVec<Msb0, u8> {
+        match self {
+            Mode::Numeric => bitvec![Msb0, u8;0, 0, 1],
+            Mode::Alphanumeric => bitvec![Msb0, u8;0, 1, 0],
+            Mode::Byte => bitvec![Msb0, u8;0, 1, 1],
+            Mode::Kanji => bitvec![Msb0, u8;1, 0, 0],
+        }
+    }
+
+    /// Returns the char count of the string, as stored in the QR code.
+    /// It's the count of bytes for the byte mode, in ISO-8859-1 if possible otherwise UTF-8.
+    pub fn char_count(&self, s: &str) -> usize {
         match self {
-            Mode::Byte => bitvec![Lsb0, u8;0, 1, 0, 0],
+            Mode::Kanji => s.chars().count(),
+            Mode::Byte if Mode::in_latin1(s) => s.chars().count(),
+            _ => s.len(),
         }
     }
 
+    /// Returns true if contents can be represented by the numeric mode.
+    pub fn in_numeric(s: &str) -> bool {
+        s.bytes().all(|x| x.is_ascii_digit())
+    }
+
+    /// Returns true if contents can be represented by the alphanumeric mode.
+    pub fn in_alphanumeric(s: &str) -> bool {
+        s.chars().all(|x| alphanumeric_value(x).is_some())
+    }
+
+    /// Returns true if contents can be represented by the kanji mode.
+    pub fn in_kanji(s: &str) -> bool {
+        s.chars().all(|x| sjis::from_char(x).is_some())
+    }
+
+    /// Returns true if contents can be represented in ISO-8859-1,
+    /// the default character set of the byte mode.
+    pub fn in_latin1(s: &str) -> bool {
+        s.chars().all(|x| (x as u32) <= 0xFF)
+    }
+
     /// Returns true if contents can be represented by the byte mode.
     pub fn in_byte(_s: &str) -> bool {
         true
     }
 }
+
+/// Returns the value of a char in the alphanumeric mode, if it's supported.
+pub fn alphanumeric_value(c: char) -> Option<u8> {
+    ALPHANUMERIC.iter().position(|x| *x as char == c).map(|x| x as u8)
+}
+
+/// Returns the char with a value in the alphanumeric mode, if there is one.
+pub fn alphanumeric_char(v: u8) -> Option<char> {
+    ALPHANUMERIC.get(v as usize).map(|x| *x as char)
+}
+
+// Chars supported by the alphanumeric mode, their value is the position.
+static ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
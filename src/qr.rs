@@ -20,6 +20,7 @@ pub struct Qr {
     pub ecl: ECLevel,
 
     /// Encoding mode.
+    /// If the data is split into several segments, it's the mode of the first segment.
     pub mode: Mode,
 
     /// The modules.
@@ -31,11 +32,9 @@ pub struct Qr {
 
 impl Qr {
     /// Create a new QR from a string.
+    /// The smallest version that can hold the string is used.
     pub fn new(s: &str) -> Result<Qr, Error> {
-        QrBuilder::new()
-            .version(Version::new(3))
-            .ecl(ECLevel::L)
-            .into(s)
+        QrBuilder::new().ecl(ECLevel::L).into(s)
     }
 
     /// Returns the size of the QR code.
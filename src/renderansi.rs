@@ -0,0 +1,168 @@
+//! Renders the QR code to text with ANSI color escape sequences.
+//!
+//! Two module rows are packed into one line with the upper half block,
+//! colored with the foreground for the top row and the background for the bottom row.
+use crate::matrix::Matrix;
+use crate::qr::Qr;
+use crate::rendercommons::QuietZone;
+use crate::*;
+
+/// A string renderer for converting a QR code into colored terminal output.
+/// Terminals can't blend colors, so alpha is ignored.
+pub struct AnsiRenderer {
+    light: rendercommons::Color,
+    dark: rendercommons::Color,
+    true_color: bool,
+    qz: QuietZone,
+    allow_inverted: bool,
+}
+
+impl Default for AnsiRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnsiRenderer {
+    /// Create a new renderer.
+    pub fn new() -> Self {
+        Self {
+            light: rendercommons::Color::new(255, 255, 255),
+            dark: rendercommons::Color::new(0, 0, 0),
+            true_color: true,
+            qz: QuietZone::qr(),
+            allow_inverted: false,
+        }
+    }
+
+    /// Set the light module color.
+    /// Will also be the color of the quiet zone, if relevant.
+    pub fn light_module(mut self, v: Color) -> Self {
+        self.light = v;
+        self
+    }
+
+    /// Set the dark module color.
+    pub fn dark_module(mut self, v: Color) -> Self {
+        self.dark = v;
+        self
+    }
+
+    /// Set if 24-bit colors are used.
+    /// Otherwise the colors are approximated with the 256 color palette.
+    pub fn true_color(mut self, v: bool) -> Self {
+        self.true_color = v;
+        self
+    }
+
+    /// Set the quiet zone, as a module count or `true` for the 4 modules of QR codes.
+    pub fn quiet_zone(mut self, v: impl Into<QuietZone>) -> Self {
+        self.qz = v.into();
+        self
+    }
+
+    /// Set if `validate` accepts dark modules lighter than the light ones, for codes meant to be inverted.
+    pub fn allow_inverted(mut self, v: bool) -> Self {
+        self.allow_inverted = v;
+        self
+    }
+
+    /// Check that the dark module color can be scanned against the light color.
+    /// See `validate_contrast`.
+    pub fn validate(&self) -> Result<(), ContrastError> {
+        validate_contrast(self.dark, self.light, self.allow_inverted)
+    }
+
+    /// Render QR to string.
+    ///
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let qr = Qr::new("HELLO WORLD").unwrap();
+    /// let s = AnsiRenderer::new().dark_module(Color::hex(0x000080)).render(&qr);
+    /// assert!(s.contains("38;2;0;0;128"));
+    /// // Every line resets the terminal colors.
+    /// assert!(s.lines().all(|line| line.ends_with("\x1b[0m")));
+    /// ```
+    pub fn render(&self, qr: &Qr) -> String {
+        self.render_matrix(&qr.matrix)
+    }
+
+    /// Render matrix to string.
+    pub fn render_matrix(&self, matrix: &Matrix) -> String {
+        let w = self.qz.width(matrix);
+        let h = self.qz.height(matrix);
+        let mut res = String::new();
+        for y in (0..h).step_by(2) {
+            // Only emit escapes when the colors change.
+            let mut current = None;
+            for x in 0..w {
+                let top = self.color(matrix, x, y);
+                // An odd last row leaves the bottom half with the terminal background.
+                let bottom = if y + 1 < h { Some(self.color(matrix, x, y + 1)) } else { None };
+                if current != Some((top, bottom)) {
+                    res.push_str("\x1b[");
+                    res.push_str(&self.escape(38, top));
+                    if let Some(bottom) = bottom {
+                        res.push(';');
+                        res.push_str(&self.escape(48, bottom));
+                    }
+                    res.push('m');
+                    current = Some((top, bottom));
+                }
+                res.push('▀');
+            }
+            res.push_str("\x1b[0m\n");
+        }
+        res
+    }
+
+    // The color of the module at x and y, counted with the quiet zone.
+    fn color(&self, matrix: &Matrix, x: usize, y: usize) -> Color {
+        if self.qz.is_dark(matrix, x, y) {
+            self.dark
+        } else {
+            self.light
+        }
+    }
+
+    // Select graphic rendition parameters for a color,
+    // with 38 for foreground or 48 for background.
+    fn escape(&self, kind: u8, c: Color) -> String {
+        if self.true_color {
+            format!("{};2;{};{};{}", kind, c.r, c.g, c.b)
+        } else {
+            format!("{};5;{}", kind, to_ansi_256(c))
+        }
+    }
+}
+
+// The closest color from the 6x6x6 cube or the gray ramp of the 256 color palette.
+fn to_ansi_256(c: Color) -> u8 {
+    let level = |v: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, l)| (**l as i32 - v as i32).abs())
+            .unwrap()
+            .0
+    };
+    let (r, g, b) = (level(c.r), level(c.g), level(c.b));
+    let cube = Color::new(CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+
+    let avg = (c.r as usize + c.g as usize + c.b as usize) / 3;
+    let gray = (avg.saturating_sub(3) / 10).min(23);
+    let v = (8 + 10 * gray) as u8;
+
+    let distance = |o: Color| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(c.r, o.r) + d(c.g, o.g) + d(c.b, o.b)
+    };
+    if distance(Color::new(v, v, v)) < distance(cube) {
+        232 + gray as u8
+    } else {
+        (16 + 36 * r + 6 * g + b) as u8
+    }
+}
+
+// Channel values of the 6x6x6 color cube, palette entries 16 to 231.
+static CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
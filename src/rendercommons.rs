@@ -3,17 +3,17 @@
 //! Outputs to a string representation and svg are supported.
 use crate::matrix::{Matrix, Module};
 
+use std::fmt;
 use std::num::ParseIntError;
 use std::str::FromStr;
-use std::u8;
 
 /// Convert to string, with chars for the different underlying representations.
 pub fn to_dbg_string(matrix: &Matrix) -> String {
-    let mut res = String::with_capacity(matrix.size * matrix.size);
+    let mut res = String::with_capacity(matrix.width * matrix.height);
     res.push('\n');
-    for y in 0..matrix.size {
-        let mut s = String::with_capacity(matrix.size + 1);
-        for x in 0..matrix.size {
+    for y in 0..matrix.height {
+        let mut s = String::with_capacity(matrix.width + 1);
+        for x in 0..matrix.width {
             let c = match matrix.get(x, y) {
                 Module::Unknown => '?',
                 Module::Reserved => '*',
@@ -31,17 +31,97 @@ pub fn to_dbg_string(matrix: &Matrix) -> String {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-/// An RGB color implementation.
+/// The quiet zone around a code, as the module count on every side.
+///
+/// ```
+/// # use qrcode53bytes::*;
+/// let qr = Qr::new("HELLO WORLD").unwrap();
+/// let s = StringRenderer::new().quiet_zone(2).render(&qr);
+/// assert_eq!(s.lines().count(), 21 + 2 * 2);
+///
+/// let micro = MicroQr::new("12345").unwrap();
+/// let svg = SvgRenderer::new().quiet_zone(QuietZone::micro()).render_matrix(&micro.matrix);
+/// ```
+pub struct QuietZone(pub usize);
+
+impl QuietZone {
+    /// Create a new quiet zone, with the module count on every side.
+    pub fn new(v: usize) -> Self {
+        Self(v)
+    }
+
+    /// No quiet zone.
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    /// The 4 modules required around QR codes.
+    pub fn qr() -> Self {
+        Self(4)
+    }
+
+    /// The 2 modules required around Micro QR and rMQR codes.
+    pub fn micro() -> Self {
+        Self(2)
+    }
+
+    /// Width of the matrix including the quiet zone, in modules.
+    pub fn width(&self, matrix: &Matrix) -> usize {
+        matrix.width + 2 * self.0
+    }
+
+    /// Height of the matrix including the quiet zone, in modules.
+    pub fn height(&self, matrix: &Matrix) -> usize {
+        matrix.height + 2 * self.0
+    }
+
+    /// If the module at x and y, counted from the outer edge of the quiet zone, is dark.
+    pub fn is_dark(&self, matrix: &Matrix, x: usize, y: usize) -> bool {
+        let (mx, my) = (x.wrapping_sub(self.0), y.wrapping_sub(self.0));
+        mx < matrix.width && my < matrix.height && matrix.is_dark(mx, my)
+    }
+}
+
+impl From<bool> for QuietZone {
+    /// The QR code quiet zone if true, otherwise none.
+    fn from(v: bool) -> Self {
+        if v {
+            Self::qr()
+        } else {
+            Self::none()
+        }
+    }
+}
+
+impl From<usize> for QuietZone {
+    fn from(v: usize) -> Self {
+        Self(v)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// An RGBA color implementation.
 pub struct Color {
     pub r: u8,
     pub g: u8,
     pub b: u8,
+    /// Alpha, 0 is fully transparent and 255 opaque.
+    pub a: u8,
 }
 
 impl Color {
-    /// Create a new from rgb parts.
+    /// Create a new opaque color from rgb parts.
     pub fn new(r: u8, g: u8, b: u8) -> Self {
-        Self { r, g, b }
+        Self { r, g, b, a: 255 }
+    }
+
+    /// Create a new from rgba parts.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let transparent = Color::rgba(255, 255, 255, 0);
+    /// ```
+    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
     }
 
     /// Create a new color from a hex input.
@@ -54,6 +134,7 @@ impl Color {
             r: (v >> 16) as u8,
             g: (v >> 8) as u8,
             b: v as u8,
+            a: 255,
         }
     }
 
@@ -64,45 +145,152 @@ impl Color {
     /// let c = Color::from_4_hex("#700");
     /// ```
     pub fn from_4_hex(s: &str) -> Result<Self, ParseColorError> {
-        let chars: Vec<char> = s.chars().collect();
-        if chars[0] != '#' {
+        if s.get(0..1) != Some("#") {
             return Err(ParseColorError);
         }
-        let r = u8::from_str_radix(&chars[1].to_string(), 16)?;
-        let g = u8::from_str_radix(&chars[2].to_string(), 16)?;
-        let b = u8::from_str_radix(&chars[3].to_string(), 16)?;
+        let r = u8::from_str_radix(s.get(1..2).ok_or(ParseColorError)?, 16)?;
+        let g = u8::from_str_radix(s.get(2..3).ok_or(ParseColorError)?, 16)?;
+        let b = u8::from_str_radix(s.get(3..4).ok_or(ParseColorError)?, 16)?;
         Ok(Color {
             r: (r << 4) | r,
             g: (g << 4) | g,
             b: (b << 4) | b,
+            a: 255,
         })
     }
 
+    /// Create a new color from a length 5 input hex, with alpha.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// // Short for "#77000088"
+    /// let c = Color::from_5_hex("#7008");
+    /// ```
+    pub fn from_5_hex(s: &str) -> Result<Self, ParseColorError> {
+        let a = u8::from_str_radix(s.get(4..5).ok_or(ParseColorError)?, 16)?;
+        let c = Color::from_4_hex(s.get(0..4).ok_or(ParseColorError)?)?;
+        Ok(Color { a: (a << 4) | a, ..c })
+    }
+
     /// Create a new color from a length 7 input hex.
     /// ```
     /// # use qrcode53bytes::*;
     /// let c = Color::from_7_hex("#3477ff");
     /// ```
     pub fn from_7_hex(s: &str) -> Result<Self, ParseColorError> {
-        if s[0..1] != *"#" {
+        if s.get(0..1) != Some("#") {
             return Err(ParseColorError);
         }
-        let r = u8::from_str_radix(&s[1..3], 16)?;
-        let g = u8::from_str_radix(&s[3..5], 16)?;
-        let b = u8::from_str_radix(&s[5..7], 16)?;
-        Ok(Color { r, g, b })
+        let r = u8::from_str_radix(s.get(1..3).ok_or(ParseColorError)?, 16)?;
+        let g = u8::from_str_radix(s.get(3..5).ok_or(ParseColorError)?, 16)?;
+        let b = u8::from_str_radix(s.get(5..7).ok_or(ParseColorError)?, 16)?;
+        Ok(Color { r, g, b, a: 255 })
+    }
+
+    /// Create a new color from a length 9 input hex, with alpha.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let c = Color::from_9_hex("#3477ff80").unwrap();
+    /// assert_eq!(c, Color::rgba(0x34, 0x77, 0xff, 0x80));
+    /// assert_eq!("#3477ff80".parse::<Color>().unwrap(), c);
+    /// // Lengths are in bytes, so other characters fail instead of panicking.
+    /// assert!("#1é234ff".parse::<Color>().is_err());
+    /// assert!("#é12".parse::<Color>().is_err());
+    /// ```
+    pub fn from_9_hex(s: &str) -> Result<Self, ParseColorError> {
+        let a = u8::from_str_radix(s.get(7..9).ok_or(ParseColorError)?, 16)?;
+        let c = Color::from_7_hex(s.get(0..7).ok_or(ParseColorError)?)?;
+        Ok(Color { a, ..c })
     }
 
-    /// Convert to a hex string.
+    /// Convert to a hex string, without alpha.
     /// ```
     /// # use qrcode53bytes::*;
     /// assert_eq!(Color::hex(0xff7312).to_hex_str(), "#ff7312");
     /// ```
     pub fn to_hex_str(&self) -> String {
-        String::from(format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b))
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Relative luminance, from 0 for black to 1 for white, as defined by WCAG.
+    /// Alpha is ignored.
+    pub fn luminance(&self) -> f64 {
+        let channel = |v: u8| {
+            let v = v as f64 / 255.0;
+            if v <= 0.03928 {
+                v / 12.92
+            } else {
+                ((v + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * channel(self.r) + 0.7152 * channel(self.g) + 0.0722 * channel(self.b)
+    }
+
+    /// Contrast ratio between two colors, from 1 for equal luminance to 21 for black and white.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let ratio = Color::hex(0x000000).contrast(&Color::hex(0xffffff));
+    /// assert!((ratio - 21.0).abs() < 1e-9);
+    /// ```
+    pub fn contrast(&self, other: &Color) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Is the color fully transparent?
+    pub fn is_transparent(&self) -> bool {
+        self.a == 0
+    }
+
+    /// Is the color fully opaque?
+    pub fn is_opaque(&self) -> bool {
+        self.a == 255
     }
 }
 
+/// Check that dark modules in the dark color can be told apart from light modules in the light color.
+///
+/// Fails if the contrast is below `MIN_CONTRAST`, or if the dark color is lighter than the light one,
+/// unless inverted codes are allowed. Alpha is ignored, so check transparent colors
+/// with the color they're drawn on.
+/// ```
+/// # use qrcode53bytes::*;
+/// let (black, white) = (Color::hex(0x000000), Color::hex(0xffffff));
+/// assert_eq!(validate_contrast(black, white, false), Ok(()));
+/// assert_eq!(validate_contrast(white, black, false), Err(ContrastError::Inverted));
+/// assert_eq!(validate_contrast(white, black, true), Ok(()));
+/// assert!(validate_contrast(Color::hex(0xaaaaaa), white, false).is_err());
+/// ```
+pub fn validate_contrast(dark: Color, light: Color, allow_inverted: bool) -> Result<(), ContrastError> {
+    let ratio = dark.contrast(&light);
+    if ratio < MIN_CONTRAST {
+        Err(ContrastError::LowContrast(ratio))
+    } else if !allow_inverted && dark.luminance() > light.luminance() {
+        Err(ContrastError::Inverted)
+    } else {
+        Ok(())
+    }
+}
+
+/// Colors that make a code hard or impossible to scan.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ContrastError {
+    /// The contrast ratio between the dark and light colors is below `MIN_CONTRAST`.
+    LowContrast(f64),
+    /// The dark modules are lighter than the light modules, which many scanners don't read.
+    Inverted,
+}
+
+impl fmt::Display for ContrastError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContrastError::LowContrast(ratio) => write!(f, "contrast ratio {:.2} is below the minimum of {}", ratio, MIN_CONTRAST),
+            ContrastError::Inverted => write!(f, "dark modules are lighter than light modules"),
+        }
+    }
+}
+
+impl std::error::Error for ContrastError {}
+
 #[derive(Debug, Copy, Clone)]
 /// An error from trying to parse a Color instance from string.
 pub struct ParseColorError;
@@ -112,7 +300,9 @@ impl FromStr for Color {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.len() {
             4 => Color::from_4_hex(s),
+            5 => Color::from_5_hex(s),
             7 => Color::from_7_hex(s),
+            9 => Color::from_9_hex(s),
             _ => Err(ParseColorError),
         }
     }
@@ -123,3 +313,7 @@ impl From<ParseIntError> for ParseColorError {
         ParseColorError
     }
 }
+
+/// The lowest contrast ratio between dark and light colors considered scannable.
+/// Like the WCAG minimum for graphics.
+pub const MIN_CONTRAST: f64 = 3.0;
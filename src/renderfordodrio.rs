@@ -3,19 +3,26 @@
 //! Outputs to a string representation and svg are supported.
 use crate::matrix::Matrix;
 use crate::qr::Qr;
+use crate::rendercommons::QuietZone;
 //use crate::*;
 
 /// A string renderer for converting a QR code into svg.
 pub struct SvgDodrioRenderer {
     w: usize,
     h: usize,
-    qz: bool,
+    qz: QuietZone,
 }
 
 impl SvgDodrioRenderer {
     /// Create a new renderer.
     pub fn new(w: usize, h: usize) -> Self {
-        Self { w, h, qz: true }
+        Self { w, h, qz: QuietZone::qr() }
+    }
+
+    /// Set the quiet zone, as a module count or `true` for the 4 modules of QR codes.
+    pub fn quiet_zone(mut self, v: impl Into<QuietZone>) -> Self {
+        self.qz = v.into();
+        self
     }
 
     /// Render QR.
@@ -25,15 +32,12 @@ impl SvgDodrioRenderer {
 
     /// Render matrix.
     pub fn render_matrix(&self, matrix: &Matrix) -> String {
-        let cell_count = if self.qz {
-            matrix.size + 8
-        } else {
-            matrix.size
-        };
+        let cell_count_x = self.qz.width(matrix);
+        let cell_count_y = self.qz.height(matrix);
         // If not divided evenly adjust upwards and treat specified
         // width and height as minimums.
-        let cell_w = ((self.w as f64) / (cell_count as f64)).ceil() as usize;
-        let cell_h = ((self.h as f64) / (cell_count as f64)).ceil() as usize;
+        let cell_w = ((self.w as f64) / (cell_count_x as f64)).ceil() as usize;
+        let cell_h = ((self.h as f64) / (cell_count_y as f64)).ceil() as usize;
 
         //only for debugging
         /*
@@ -49,19 +53,11 @@ impl SvgDodrioRenderer {
                 ));
         */
         let mut res = r##"<path fill="#000000" d=" "##.to_string();
-        for y in 0..matrix.size {
-            let yp = if self.qz {
-                (y + 4) * cell_h
-            } else {
-                y * cell_h
-            };
+        for y in 0..matrix.height {
+            let yp = (y + self.qz.0) * cell_h;
 
-            for x in 0..matrix.size {
-                let xp = if self.qz {
-                    (x + 4) * cell_w
-                } else {
-                    x * cell_w
-                };
+            for x in 0..matrix.width {
+                let xp = (x + self.qz.0) * cell_w;
 
                 if matrix.is_dark(x, y) {
                     res.push_str(
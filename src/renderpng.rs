@@ -0,0 +1,162 @@
+//! Renders the QR code to a png image.
+//!
+//! The image uses a two color palette, one bit per pixel.
+//! Colors with alpha make the palette transparent.
+use crate::matrix::Matrix;
+use crate::qr::Qr;
+use crate::rendercommons::QuietZone;
+use crate::*;
+use lazy_static::lazy_static;
+
+/// A renderer for converting a QR code into png bytes.
+pub struct PngRenderer {
+    light: rendercommons::Color,
+    dark: rendercommons::Color,
+    w: usize,
+    h: usize,
+    qz: QuietZone,
+    allow_inverted: bool,
+}
+
+impl Default for PngRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PngRenderer {
+    /// Create a new renderer.
+    pub fn new() -> Self {
+        Self {
+            light: rendercommons::Color::new(255, 255, 255),
+            dark: rendercommons::Color::new(0, 0, 0),
+            w: 200,
+            h: 200,
+            qz: QuietZone::qr(),
+            allow_inverted: false,
+        }
+    }
+
+    /// Set the light module color.
+    /// Will also be the color of the quiet zone, if relevant.
+    pub fn light_module(mut self, v: Color) -> Self {
+        self.light = v;
+        self
+    }
+
+    /// Set the dark module color.
+    pub fn dark_module(mut self, v: Color) -> Self {
+        self.dark = v;
+        self
+    }
+
+    /// Set the quiet zone, as a module count or `true` for the 4 modules of QR codes.
+    pub fn quiet_zone(mut self, v: impl Into<QuietZone>) -> Self {
+        self.qz = v.into();
+        self
+    }
+
+    /// Set the dimensions of the output, in pixels.
+    /// Includes the quiet zone, if relevant.
+    pub fn dimensions(mut self, w: usize, h: usize) -> Self {
+        self.w = w;
+        self.h = h;
+        self
+    }
+
+    /// Set if `validate` accepts dark modules lighter than the light ones, for codes meant to be inverted.
+    pub fn allow_inverted(mut self, v: bool) -> Self {
+        self.allow_inverted = v;
+        self
+    }
+
+    /// Check that the dark module color can be scanned against the light color.
+    /// See `validate_contrast`.
+    pub fn validate(&self) -> Result<(), ContrastError> {
+        validate_contrast(self.dark, self.light, self.allow_inverted)
+    }
+
+    /// Render QR.
+    ///
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let qr = Qr::new("HELLO WORLD").unwrap();
+    /// let png = PngRenderer::new().dimensions(100, 100).render(&qr);
+    /// assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    /// ```
+    pub fn render(&self, qr: &Qr) -> Vec<u8> {
+        self.render_matrix(&qr.matrix)
+    }
+
+    /// Render matrix.
+    pub fn render_matrix(&self, matrix: &Matrix) -> Vec<u8> {
+        let cell_count_x = self.qz.width(matrix);
+        let cell_count_y = self.qz.height(matrix);
+        // If not divided evenly adjust upwards and treat specified
+        // width and height as minimums.
+        let cell_w = ((self.w as f64) / (cell_count_x as f64)).ceil() as usize;
+        let cell_h = ((self.h as f64) / (cell_count_y as f64)).ceil() as usize;
+        // We might grow larger so readjust dimensions.
+        let w = cell_w * cell_count_x;
+        let h = cell_h * cell_count_y;
+
+        // Each row starts with filter type 0, then 8 pixels per byte.
+        // Palette index 0 is light and 1 is dark.
+        let row_len = 1 + w.div_ceil(8);
+        let mut pixels = vec![0; row_len * h];
+        for y in 0..h {
+            let row = &mut pixels[y * row_len + 1..(y + 1) * row_len];
+            for x in 0..w {
+                if self.qz.is_dark(matrix, x / cell_w, y / cell_h) {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(w as u32).to_be_bytes());
+        header.extend_from_slice(&(h as u32).to_be_bytes());
+        // Bit depth 1, indexed color, default compression, filter and no interlace.
+        header.extend_from_slice(&[1, 3, 0, 0, 0]);
+
+        let palette = [self.light.r, self.light.g, self.light.b, self.dark.r, self.dark.g, self.dark.b];
+
+        let mut res = SIGNATURE.to_vec();
+        push_chunk(&mut res, b"IHDR", &header);
+        push_chunk(&mut res, b"PLTE", &palette);
+        // Alpha of the palette entries, for transparent colors.
+        if !self.light.is_opaque() || !self.dark.is_opaque() {
+            push_chunk(&mut res, b"tRNS", &[self.light.a, self.dark.a]);
+        }
+        push_chunk(&mut res, b"IDAT", &zlib::compress(&pixels));
+        push_chunk(&mut res, b"IEND", &[]);
+        res
+    }
+}
+
+// A chunk is length, type, data and a crc over type and data.
+fn push_chunk(res: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    res.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = res.len();
+    res.extend_from_slice(kind);
+    res.extend_from_slice(data);
+    let crc = crc32(&res[start..]);
+    res.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let crc = data
+        .iter()
+        .fold(0xFFFF_FFFF, |crc, x| CRC_TABLE[((crc ^ *x as u32) & 0xFF) as usize] ^ crc >> 8);
+    crc ^ 0xFFFF_FFFF
+}
+
+// Every png starts with these bytes.
+static SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+lazy_static! {
+    // Crc of every byte value, with the reversed polynomial 0xEDB88320.
+    static ref CRC_TABLE: Vec<u32> = (0..256u32)
+        .map(|n| (0..8).fold(n, |c, _| if c & 1 == 1 { 0xEDB8_8320 ^ c >> 1 } else { c >> 1 }))
+        .collect();
+}
@@ -3,6 +3,7 @@
 //! Outputs to a string representation and svg are supported.
 use crate::matrix::Matrix;
 use crate::qr::Qr;
+use crate::rendercommons::QuietZone;
 
 /// A string renderer for converting a QR code into a representation
 /// suitable for text output.
@@ -11,7 +12,13 @@ pub struct StringRenderer {
     dark: char,
     module_w: usize,
     module_h: usize,
-    qz: bool,
+    qz: QuietZone,
+}
+
+impl Default for StringRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl StringRenderer {
@@ -22,7 +29,7 @@ impl StringRenderer {
             dark: '#',
             module_w: 1,
             module_h: 1,
-            qz: false,
+            qz: QuietZone::none(),
         }
     }
 
@@ -38,9 +45,9 @@ impl StringRenderer {
         self
     }
 
-    /// Set if quiet zone should be produced.
-    pub fn quiet_zone(mut self, v: bool) -> Self {
-        self.qz = v;
+    /// Set the quiet zone, as a module count or `true` for the 4 modules of QR codes.
+    pub fn quiet_zone(mut self, v: impl Into<QuietZone>) -> Self {
+        self.qz = v.into();
         self
     }
 
@@ -59,14 +66,14 @@ impl StringRenderer {
 
     /// Render matrix to string.
     pub fn render_matrix(&self, matrix: &Matrix) -> String {
-        let mut res = String::with_capacity(matrix.size * matrix.size);
+        let mut res = String::with_capacity(matrix.width * matrix.height);
         self.qz_lines(&mut res);
-        for y in 0..matrix.size {
+        for y in 0..matrix.height {
             // Duplicate rows for larger module dimensions.
             for _ in 0..self.module_h {
-                let mut s = String::with_capacity(matrix.size + 1);
+                let mut s = String::with_capacity(matrix.width + 1);
                 self.qz_chars(&mut s);
-                for x in 0..matrix.size {
+                for x in 0..matrix.width {
                     let c = if matrix.is_dark(x, y) {
                         self.dark
                     } else {
@@ -88,19 +95,15 @@ impl StringRenderer {
 
     // Append empty lines for quiet zone padding.
     fn qz_lines(&self, s: &mut String) {
-        if self.qz {
-            for _ in 0..(4 * self.module_h) {
-                s.push_str("\n");
-            }
+        for _ in 0..(self.qz.0 * self.module_h) {
+            s.push('\n');
         }
     }
 
     // Append whitespace chars for quiet zone padding.
     fn qz_chars(&self, s: &mut String) {
-        if self.qz {
-            for _ in 0..(4 * self.module_w) {
-                s.push(' ');
-            }
+        for _ in 0..(self.qz.0 * self.module_w) {
+            s.push(' ');
         }
     }
 }
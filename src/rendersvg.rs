@@ -1,17 +1,75 @@
 //! Renders the QR code to different outputs.
 //!
 //! Outputs to a string representation and svg are supported.
-use crate::matrix::Matrix;
+use crate::builder::ZigZagIt;
+use crate::matrix::{Matrix, Module};
 use crate::qr::Qr;
+use crate::rendercommons::QuietZone;
 use crate::*;
 
+/// The shape of the data modules in svg output.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ModuleShape {
+    /// Squares filling the whole module.
+    Square,
+    /// Circles touching the module edges.
+    Circle,
+    /// Squares with rounded corners.
+    RoundedSquare,
+    /// Squares rounded only at corners without dark neighbours,
+    /// so connected modules flow into each other.
+    ConnectedRounded,
+}
+
+/// The shape of the finder patterns, the eyes in the corners, in svg output.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FinderShape {
+    /// Square rings and centers, as specified.
+    Square,
+    /// Squares with rounded corners.
+    Rounded,
+    /// Circular rings and centers.
+    Circle,
+}
+
+/// A gradient fill for the dark modules in svg output, from the first to the second color.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Gradient {
+    /// A linear gradient across the symbol, at an angle in degrees.
+    /// 0 goes from left to right and 90 from top to bottom.
+    Linear(Color, Color, f64),
+    /// A radial gradient from the center of the symbol to its corners.
+    Radial(Color, Color),
+}
+
+/// An image placed in the center of the svg output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Logo {
+    /// Svg elements, drawn in a 100 by 100 view box scaled to the logo area.
+    Svg(String),
+    /// A png image as a data uri, like `data:image/png;base64,iVBORw0KGgo...`.
+    PngDataUri(String),
+}
+
 /// A string renderer for converting a QR code into svg.
 pub struct SvgRenderer {
     light: rendercommons::Color,
     dark: rendercommons::Color,
     w: usize,
     h: usize,
-    qz: bool,
+    qz: QuietZone,
+    optimize: bool,
+    module_shape: ModuleShape,
+    finder_shape: FinderShape,
+    gradient: Option<Gradient>,
+    finder_color: Option<Color>,
+    allow_inverted: bool,
+}
+
+impl Default for SvgRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SvgRenderer {
@@ -22,12 +80,50 @@ impl SvgRenderer {
             dark: rendercommons::Color::new(0, 0, 0),
             w: 200,
             h: 200,
-            qz: true,
+            qz: QuietZone::qr(),
+            optimize: false,
+            module_shape: ModuleShape::Square,
+            finder_shape: FinderShape::Square,
+            gradient: None,
+            finder_color: None,
+            allow_inverted: false,
         }
     }
 
+    /// Set a gradient fill for the dark modules, used instead of the dark module color.
+    ///
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let qr = Qr::new("HELLO WORLD").unwrap();
+    /// let svg = SvgRenderer::new()
+    ///     .dark_gradient(Gradient::Linear(Color::hex(0x003366), Color::hex(0x6600cc), 45.0))
+    ///     .finder_color(Color::hex(0xcc0000))
+    ///     .render(&qr);
+    /// assert!(svg.contains("<linearGradient"));
+    /// assert!(svg.contains("fill=\"#cc0000\""));
+    /// ```
+    pub fn dark_gradient(mut self, v: Gradient) -> Self {
+        self.gradient = Some(v);
+        self
+    }
+
+    /// Set the color of the finder patterns, instead of the dark module color or gradient.
+    /// Finders are found like with `finder_shape`.
+    pub fn finder_color(mut self, v: Color) -> Self {
+        self.finder_color = Some(v);
+        self
+    }
+
     /// Set the light module color.
     /// Will also be the color of the quiet zone, if relevant.
+    /// The background is left out if the color is fully transparent.
+    ///
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let qr = Qr::new("HELLO WORLD").unwrap();
+    /// let svg = SvgRenderer::new().light_module("#fff0".parse().unwrap()).render(&qr);
+    /// assert!(!svg.contains("<rect"));
+    /// ```
     pub fn light_module(mut self, v: Color) -> Self {
         self.light = v;
         self
@@ -39,9 +135,9 @@ impl SvgRenderer {
         self
     }
 
-    /// Set if quiet zone should be produced.
-    pub fn quiet_zone(mut self, v: bool) -> Self {
-        self.qz = v;
+    /// Set the quiet zone, as a module count or `true` for the 4 modules of QR codes.
+    pub fn quiet_zone(mut self, v: impl Into<QuietZone>) -> Self {
+        self.qz = v.into();
         self
     }
 
@@ -53,53 +149,303 @@ impl SvgRenderer {
         self
     }
 
+    /// Set if the path should trace the outlines of connected dark areas,
+    /// instead of drawing every dark module on its own.
+    /// The output is smaller and has no seams between modules when scaled.
+    pub fn optimize(mut self, v: bool) -> Self {
+        self.optimize = v;
+        self
+    }
+
+    /// Set the shape of the data modules.
+    /// Function modules, like timing and alignment patterns, are always square to keep the code scannable.
+    pub fn module_shape(mut self, v: ModuleShape) -> Self {
+        self.module_shape = v;
+        self
+    }
+
+    /// Set the shape of the finder patterns.
+    /// Finders are found among the function modules, so rendering a matrix
+    /// with only data modules leaves them like the rest.
+    ///
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let qr = Qr::new("HELLO WORLD").unwrap();
+    /// let svg = SvgRenderer::new()
+    ///     .module_shape(ModuleShape::Circle)
+    ///     .finder_shape(FinderShape::Rounded)
+    ///     .render(&qr);
+    /// // The three finders are drawn in a path of their own.
+    /// assert_eq!(svg.matches("<path").count(), 2);
+    /// ```
+    pub fn finder_shape(mut self, v: FinderShape) -> Self {
+        self.finder_shape = v;
+        self
+    }
+
+    /// Set if `validate` accepts dark modules lighter than the light ones, for codes meant to be inverted.
+    pub fn allow_inverted(mut self, v: bool) -> Self {
+        self.allow_inverted = v;
+        self
+    }
+
+    /// Check that the dark module, gradient and finder colors can be scanned against the light color.
+    /// See `validate_contrast`.
+    ///
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let renderer = SvgRenderer::new().dark_module(Color::hex(0xffffff)).light_module(Color::hex(0x000000));
+    /// assert_eq!(renderer.validate(), Err(ContrastError::Inverted));
+    /// assert_eq!(renderer.allow_inverted(true).validate(), Ok(()));
+    /// ```
+    pub fn validate(&self) -> Result<(), ContrastError> {
+        let mut colors = match self.gradient {
+            Some(Gradient::Linear(from, to, _)) | Some(Gradient::Radial(from, to)) => vec![from, to],
+            None => vec![self.dark],
+        };
+        colors.extend(self.finder_color);
+        for c in colors {
+            validate_contrast(c, self.light, self.allow_inverted)?;
+        }
+        Ok(())
+    }
+
     /// Render QR.
+    ///
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let qr = Qr::new("HELLO WORLD").unwrap();
+    /// let plain = SvgRenderer::new().render(&qr);
+    /// let optimized = SvgRenderer::new().optimize(true).render(&qr);
+    /// assert!(optimized.len() < plain.len() / 2);
+    /// ```
     pub fn render(&self, qr: &Qr) -> String {
         self.render_matrix(&qr.matrix)
     }
 
+    /// Render QR with a logo in the center, covering fraction of the symbol width and height.
+    /// The covered modules are cleared.
+    ///
+    /// Fails with `Error::LogoTooLarge` if the error correction level can't recover
+    /// the cleared data, or if the logo covers any function pattern, alignment patterns included.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let qr = QrBuilder::new().ecl(ECLevel::H).into("https://bestia.dev").unwrap();
+    /// let logo = Logo::Svg("<circle cx=\"50\" cy=\"50\" r=\"40\" fill=\"#e00\"/>".to_string());
+    /// let svg = SvgRenderer::new().render_with_logo(&qr, &logo, 0.2).unwrap();
+    /// assert!(svg.contains("<circle"));
+    ///
+    /// let qr = QrBuilder::new().ecl(ECLevel::L).into("https://bestia.dev").unwrap();
+    /// assert_eq!(SvgRenderer::new().render_with_logo(&qr, &logo, 0.2), Err(Error::LogoTooLarge));
+    ///
+    /// // The logo can't cover the center alignment pattern of version 7.
+    /// let qr = QrBuilder::new().version(Version::new(7)).ecl(ECLevel::H).into("https://bestia.dev").unwrap();
+    /// assert_eq!(SvgRenderer::new().render_with_logo(&qr, &logo, 0.1), Err(Error::LogoTooLarge));
+    /// ```
+    pub fn render_with_logo(&self, qr: &Qr, logo: &Logo, fraction: f64) -> Result<String, Error> {
+        let size = qr.matrix.size();
+        // Whole modules, centered.
+        let mut n = ((size as f64) * fraction).ceil() as usize;
+        if n % 2 != size % 2 {
+            n += 1;
+        }
+        let n = n.min(size);
+        let start = (size - n) / 2;
+        let covered = |x: usize, y: usize| (start..start + n).contains(&x) && (start..start + n).contains(&y);
+
+        let mut matrix = qr.matrix.clone();
+        for y in start..start + n {
+            for x in start..start + n {
+                let m = match matrix.get(x, y) {
+                    Module::Data(_) => Module::Data(false),
+                    _ => return Err(Error::LogoTooLarge),
+                };
+                matrix.set(x, y, m);
+            }
+        }
+        if !recoverable(qr, &covered) {
+            return Err(Error::LogoTooLarge);
+        }
+
+        let mut res = self.render_matrix(&matrix);
+        let (cell_w, cell_h) = self.cell_size(&matrix);
+        let (x, y) = ((start + self.qz.0) * cell_w, (start + self.qz.0) * cell_h);
+        let (w, h) = (n * cell_w, n * cell_h);
+        let element = match logo {
+            Logo::Svg(v) => format!(
+                "<svg x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" viewBox=\"0 0 100 100\">{}</svg>",
+                x, y, w, h, v
+            ),
+            Logo::PngDataUri(v) => format!(
+                "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+                 xmlns:xlink=\"http://www.w3.org/1999/xlink\" xlink:href=\"{}\"/>",
+                x, y, w, h, v
+            ),
+        };
+        // Insert before the closing tag.
+        res.truncate(res.len() - "</svg>\n".len());
+        res.push_str(&element);
+        res.push_str("</svg>\n");
+        Ok(res)
+    }
+
     /// Render matrix.
+    ///
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let code = RmqrBuilder::new().height(7).into("HELLO").unwrap();
+    /// let svg = SvgRenderer::new().finder_shape(FinderShape::Rounded).render_matrix(&code.matrix);
+    /// // The single finder is the outer ring, its hole and the center.
+    /// let finders = svg.split("fill-rule=\"evenodd\"").nth(1).unwrap();
+    /// assert_eq!(finders.matches('M').count(), 3);
+    /// ```
     pub fn render_matrix(&self, matrix: &Matrix) -> String {
-        let cell_count = if self.qz {
-            matrix.size + 8
+        let (cell_w, cell_h) = self.cell_size(matrix);
+        // We might grow larger so readjust dimensions.
+        let w = cell_w * self.qz.width(matrix);
+        let h = cell_h * self.qz.height(matrix);
+
+        // Finders with other shapes or colors and data modules with other shapes are drawn on their own.
+        let finders = if self.finder_shape == FinderShape::Square && self.finder_color.is_none() {
+            Vec::new()
         } else {
-            matrix.size
+            finder_positions(matrix)
+        };
+        let in_finder = |x: usize, y: usize| {
+            finders
+                .iter()
+                .any(|&(fx, fy)| (fx..fx + 7).contains(&x) && (fy..fy + 7).contains(&y))
+        };
+        let shaped = |x: usize, y: usize| self.module_shape != ModuleShape::Square && matrix.is_data(x, y);
+        let square = |x: usize, y: usize| matrix.is_dark(x, y) && !shaped(x, y) && !in_finder(x, y);
+        // Curves need anti-aliasing.
+        let rendering = if self.finder_shape == FinderShape::Square && self.module_shape == ModuleShape::Square {
+            "crispEdges"
+        } else {
+            "geometricPrecision"
+        };
+
+        let background = if self.light.is_transparent() {
+            String::new()
+        } else {
+            format!("<rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" {}/>", w, h, fill(self.light))
         };
-        // If not divided evenly adjust upwards and treat specified
-        // width and height as minimums.
-        let cell_w = ((self.w as f64) / (cell_count as f64)).ceil() as usize;
-        let cell_h = ((self.h as f64) / (cell_count as f64)).ceil() as usize;
-        // We might grow larger so readjust dimensions.
-        let w = cell_w * cell_count;
-        let h = cell_h * cell_count;
 
-        let mut res = String::from(format!(
+        let mut res = format!(
             "<?xml version=\"1.0\" standalone=\"yes\"?>
         <svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\"
-            viewBox=\"0 0 {w} {h}\" shape-rendering=\"crispEdges\">
-        <rect x=\"0\" y=\"0\" width=\"{w}\" height=\"{h}\" fill=\"{light}\"/>
-        <path fill=\"{dark}\" d=\"",
+            viewBox=\"0 0 {w} {h}\" shape-rendering=\"{rendering}\">
+        {defs}{background}
+        <path {dark} d=\"",
             w = w,
             h = h,
-            light = self.light.to_hex_str(),
-            dark = self.dark.to_hex_str()
-        ));
-
-        for y in 0..matrix.size {
-            let yp = if self.qz {
-                (y + 4) * cell_h
-            } else {
-                y * cell_h
-            };
+            rendering = rendering,
+            defs = self.defs(matrix, cell_w, cell_h),
+            background = background,
+            dark = self.dark_fill()
+        );
 
-            for x in 0..matrix.size {
-                let xp = if self.qz {
-                    (x + 4) * cell_w
-                } else {
-                    x * cell_w
-                };
+        if self.optimize {
+            res.push_str(&outline_path(matrix, &square, self.qz, cell_w, cell_h));
+        } else {
+            self.push_squares(&mut res, matrix, &square, cell_w, cell_h);
+        }
+
+        if self.module_shape != ModuleShape::Square {
+            for y in 0..matrix.height {
+                for x in 0..matrix.width {
+                    if shaped(x, y) && matrix.is_dark(x, y) && !in_finder(x, y) {
+                        self.push_module(&mut res, matrix, x, y, cell_w, cell_h);
+                    }
+                }
+            }
+        }
+        res.push_str("\"/>");
+
+        // Finder rings are holes in the outer shape.
+        if !finders.is_empty() {
+            let fill = self.finder_color.map(fill).unwrap_or_else(|| self.dark_fill());
+            res.push_str(&format!("<path {} fill-rule=\"evenodd\" d=\"", fill));
+            for (fx, fy) in finders {
+                self.push_finder(&mut res, fx, fy, cell_w, cell_h);
+            }
+            res.push_str("\"/>");
+        }
+        res.push_str("</svg>\n");
+        res
+    }
+
+    // The fill attributes of the dark modules, a color or a reference to the gradient.
+    fn dark_fill(&self) -> String {
+        match self.gradient {
+            Some(_) => format!("fill=\"url(#{})\"", GRADIENT_ID),
+            None => fill(self.dark),
+        }
+    }
+
+    // The gradient definition, if any. It spans the symbol without the quiet zone.
+    fn defs(&self, matrix: &Matrix, cell_w: usize, cell_h: usize) -> String {
+        let (x, y) = ((self.qz.0 * cell_w) as f64, (self.qz.0 * cell_h) as f64);
+        let (w, h) = ((matrix.width * cell_w) as f64, (matrix.height * cell_h) as f64);
+        let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+        let stops = |from: Color, to: Color| {
+            format!(
+                "<stop offset=\"0\" stop-color=\"{}\"{}/><stop offset=\"1\" stop-color=\"{}\"{}/>",
+                from.to_hex_str(),
+                opacity("stop-opacity", from),
+                to.to_hex_str(),
+                opacity("stop-opacity", to)
+            )
+        };
+        match self.gradient {
+            None => String::new(),
+            Some(Gradient::Linear(from, to, angle)) => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                // Half the length of the symbol projected on the gradient direction.
+                let len = (w / 2.0) * cos.abs() + (h / 2.0) * sin.abs();
+                format!(
+                    "<defs><linearGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" \
+                     x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">{}</linearGradient></defs>",
+                    GRADIENT_ID,
+                    cx - cos * len,
+                    cy - sin * len,
+                    cx + cos * len,
+                    cy + sin * len,
+                    stops(from, to)
+                )
+            }
+            Some(Gradient::Radial(from, to)) => format!(
+                "<defs><radialGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" \
+                 cx=\"{}\" cy=\"{}\" r=\"{}\">{}</radialGradient></defs>",
+                GRADIENT_ID,
+                cx,
+                cy,
+                (w * w + h * h).sqrt() / 2.0,
+                stops(from, to)
+            ),
+        }
+    }
+
+    // Width and height of a module, in pixels.
+    fn cell_size(&self, matrix: &Matrix) -> (usize, usize) {
+        let cell_count_x = self.qz.width(matrix);
+        let cell_count_y = self.qz.height(matrix);
+        // If not divided evenly adjust upwards and treat specified
+        // width and height as minimums.
+        let cell_w = ((self.w as f64) / (cell_count_x as f64)).ceil() as usize;
+        let cell_h = ((self.h as f64) / (cell_count_y as f64)).ceil() as usize;
+        (cell_w, cell_h)
+    }
+
+    // Append a square for every module where square is true.
+    fn push_squares(&self, res: &mut String, matrix: &Matrix, square: &dyn Fn(usize, usize) -> bool, cell_w: usize, cell_h: usize) {
+        for y in 0..matrix.height {
+            let yp = (y + self.qz.0) * cell_h;
+
+            for x in 0..matrix.width {
+                let xp = (x + self.qz.0) * cell_w;
 
-                if matrix.is_dark(x, y) {
+                if square(x, y) {
                     res.push_str(
                         format!(
                             "M{x} {y}h{w}v{h}H{x}V{y}",
@@ -113,7 +459,235 @@ impl SvgRenderer {
                 }
             }
         }
-        res.push_str("\"/></svg>\n");
-        res
+    }
+
+    // Append the data module at x and y in the module shape.
+    fn push_module(&self, res: &mut String, matrix: &Matrix, x: usize, y: usize, cell_w: usize, cell_h: usize) {
+        let (w, h) = (cell_w as f64, cell_h as f64);
+        let xp = ((x + self.qz.0) * cell_w) as f64;
+        let yp = ((y + self.qz.0) * cell_h) as f64;
+        let (corners, radius) = match self.module_shape {
+            ModuleShape::Square => ([false; 4], 0.0),
+            ModuleShape::Circle => ([true; 4], 0.5),
+            ModuleShape::RoundedSquare => ([true; 4], 0.25),
+            ModuleShape::ConnectedRounded => {
+                let dark = |dx: isize, dy: isize| {
+                    let (nx, ny) = ((x as isize + dx) as usize, (y as isize + dy) as usize);
+                    nx < matrix.width && ny < matrix.height && matrix.is_dark(nx, ny)
+                };
+                let (left, up, right, down) = (dark(-1, 0), dark(0, -1), dark(1, 0), dark(0, 1));
+                ([!left && !up, !up && !right, !right && !down, !down && !left], 0.5)
+            }
+        };
+        push_rect(res, (xp, yp, w, h), (w * radius, h * radius), corners);
+    }
+
+    // Append the finder pattern with the top left module at x and y in the finder shape.
+    fn push_finder(&self, res: &mut String, x: usize, y: usize, cell_w: usize, cell_h: usize) {
+        let (w, h) = (cell_w as f64, cell_h as f64);
+        let xp = ((x + self.qz.0) * cell_w) as f64;
+        let yp = ((y + self.qz.0) * cell_h) as f64;
+        // The outer ring, its hole and the center, in modules from the top left.
+        for &(offset, size, rounding) in &[(0.0, 7.0, 2.0), (1.0, 5.0, 1.0), (2.0, 3.0, 1.0)] {
+            let radius = match self.finder_shape {
+                FinderShape::Square => 0.0,
+                FinderShape::Rounded => rounding,
+                FinderShape::Circle => size / 2.0,
+            };
+            push_rect(
+                res,
+                (xp + offset * w, yp + offset * h, size * w, size * h),
+                (radius * w, radius * h),
+                [radius > 0.0; 4],
+            );
+        }
+    }
+}
+
+// Returns true if the data of a QR code can be recovered when the modules where lost is true can't be read.
+// Counts the damaged codewords in every block, against the ones the error correction can correct.
+fn recoverable(qr: &Qr, lost: &dyn Fn(usize, usize) -> bool) -> bool {
+    let layout = info::group_block_count(qr.version, qr.ecl);
+    let ec_count = info::block_ec_count(qr.version, qr.ecl);
+
+    // The block of every codeword, in the interleaved order.
+    let mut blocks = Vec::new();
+    let layout_max = layout.iter().max().unwrap();
+    for i in 0..*layout_max {
+        blocks.extend(layout.iter().enumerate().filter(|(_, len)| i < **len).map(|(block, _)| block));
+    }
+    for _ in 0..ec_count {
+        blocks.extend(0..layout.len());
+    }
+
+    let mut damaged = vec![false; blocks.len()];
+    let matrix = &qr.matrix;
+    let positions = ZigZagIt::new(matrix.size()).filter(|(x, y)| matrix.is_data(*x, *y));
+    for (i, (x, y)) in positions.enumerate() {
+        // Remainder bits after the last codeword don't matter.
+        if i / 8 < damaged.len() && lost(x, y) {
+            damaged[i / 8] = true;
+        }
+    }
+
+    let mut errors = vec![0; layout.len()];
+    for (block, _) in blocks.iter().zip(damaged).filter(|(_, d)| *d) {
+        errors[*block] += 1;
+    }
+    let correctable = (ec_count - misdecode_protection(qr.version, qr.ecl)) / 2;
+    errors.iter().all(|x| *x <= correctable)
+}
+
+// Error correction codewords reserved for misdecode protection in the smallest versions.
+// They can't be used to correct errors.
+fn misdecode_protection(v: Version, ecl: ECLevel) -> usize {
+    match (v.0, ecl) {
+        (1, ECLevel::L) => 3,
+        (1, ECLevel::M) | (2, ECLevel::L) => 2,
+        (1, _) | (3, ECLevel::L) => 1,
+        _ => 0,
     }
 }
+
+// The fill attributes of a color.
+fn fill(c: Color) -> String {
+    format!("fill=\"{}\"{}", c.to_hex_str(), opacity("fill-opacity", c))
+}
+
+// An opacity attribute, left out for opaque colors.
+fn opacity(name: &str, c: Color) -> String {
+    if c.is_opaque() {
+        String::new()
+    } else {
+        format!(" {}=\"{:.3}\"", name, c.a as f64 / 255.0)
+    }
+}
+
+// Append a rectangle as x, y, width and height. Corners set to true,
+// clockwise from top left, are rounded with the radius.
+fn push_rect(res: &mut String, rect: (f64, f64, f64, f64), radius: (f64, f64), corners: [bool; 4]) {
+    let (x, y, w, h) = rect;
+    let (rx, ry) = radius;
+    let r = |i: usize| if corners[i] { (rx, ry) } else { (0.0, 0.0) };
+    let arc = |res: &mut String, i: usize, ex: f64, ey: f64| {
+        if corners[i] {
+            res.push_str(&format!("A{} {} 0 0 1 {} {}", rx, ry, ex, ey));
+        }
+    };
+    res.push_str(&format!("M{} {}H{}", x + r(0).0, y, x + w - r(1).0));
+    arc(res, 1, x + w, y + r(1).1);
+    res.push_str(&format!("V{}", y + h - r(2).1));
+    arc(res, 2, x + w - r(2).0, y + h);
+    res.push_str(&format!("H{}", x + r(3).0));
+    arc(res, 3, x, y + h - r(3).1);
+    res.push_str(&format!("V{}", y + r(0).1));
+    arc(res, 0, x + r(0).0, y);
+    res.push('Z');
+}
+
+// Top left modules of the finder patterns, found among the function modules.
+fn finder_positions(matrix: &Matrix) -> Vec<(usize, usize)> {
+    if matrix.width < 7 || matrix.height < 7 {
+        return Vec::new();
+    }
+    let is_finder = |&(fx, fy): &(usize, usize)| {
+        (0..7).all(|y| {
+            (0..7).all(|x| {
+                // Only the ring between the outer ring and the center is light.
+                let ring = (x as isize - 3).abs().max((y as isize - 3).abs());
+                *matrix.get(fx + x, fy + y) == Module::Function(ring != 2)
+            })
+        })
+    };
+    let mut res: Vec<(usize, usize)> = Vec::new();
+    // Symbols only 7 modules high, like rMQR R7, have the same point twice,
+    // and drawing a finder twice with the even-odd rule would erase it.
+    for p in [(0, 0), (matrix.width - 7, 0), (0, matrix.height - 7)].iter() {
+        if !res.contains(p) && is_finder(p) {
+            res.push(*p);
+        }
+    }
+    res
+}
+
+// Trace the borders between dark and light modules into closed paths.
+// Outlines go clockwise and holes counterclockwise, so the default
+// nonzero fill rule leaves the holes empty.
+fn outline_path(matrix: &Matrix, dark: &dyn Fn(usize, usize) -> bool, qz: QuietZone, cell_w: usize, cell_h: usize) -> String {
+    // Directed border edges, as a bitmask of directions leaving every corner.
+    let (w, h) = (matrix.width + 1, matrix.height + 1);
+    let mut edges = vec![0u8; w * h];
+    let dark = |x: usize, y: usize| x < matrix.width && y < matrix.height && dark(x, y);
+    for y in 0..matrix.height {
+        for x in 0..matrix.width {
+            if !dark(x, y) {
+                continue;
+            }
+            if y == 0 || !dark(x, y - 1) {
+                edges[y * w + x] |= 1 << RIGHT;
+            }
+            if !dark(x + 1, y) {
+                edges[y * w + x + 1] |= 1 << DOWN;
+            }
+            if !dark(x, y + 1) {
+                edges[(y + 1) * w + x + 1] |= 1 << LEFT;
+            }
+            if x == 0 || !dark(x - 1, y) {
+                edges[(y + 1) * w + x] |= 1 << UP;
+            }
+        }
+    }
+
+    let mut res = String::new();
+    for start in 0..edges.len() {
+        if edges[start] == 0 {
+            continue;
+        }
+        let (mut x, mut y) = (start % w, start / w);
+        res.push_str(&format!("M{} {}", (x + qz.0) * cell_w, (y + qz.0) * cell_h));
+        let mut dir = None;
+        // Every corner has as many edges in as out, so this ends back at the start.
+        while edges[y * w + x] != 0 {
+            let out = edges[y * w + x];
+            // Where two dark modules touch diagonally there are two ways out.
+            // Keep turning right to stay with the current module.
+            // Hard assumption: an outline never turns back on itself.
+            let next = match dir {
+                Some(d) => [(d + 1) % 4, d, (d + 3) % 4].iter().copied().find(|n| out & 1u8 << n != 0).unwrap(),
+                None => out.trailing_zeros() as usize,
+            };
+            edges[y * w + x] &= !(1 << next);
+            if dir.is_some() && dir != Some(next) {
+                push_corner(&mut res, dir, x, y, qz, cell_w, cell_h);
+            }
+            match next {
+                RIGHT => x += 1,
+                DOWN => y += 1,
+                LEFT => x -= 1,
+                UP => y -= 1,
+                _ => unreachable!(),
+            }
+            dir = Some(next);
+        }
+        // Closing the path draws the last line.
+        res.push('Z');
+    }
+    res
+}
+
+// Draw a straight line in direction dir, ending at corner x and y.
+fn push_corner(res: &mut String, dir: Option<usize>, x: usize, y: usize, qz: QuietZone, cell_w: usize, cell_h: usize) {
+    match dir {
+        Some(RIGHT) | Some(LEFT) => res.push_str(&format!("H{}", (x + qz.0) * cell_w)),
+        _ => res.push_str(&format!("V{}", (y + qz.0) * cell_h)),
+    }
+}
+
+// Id of the dark module gradient in the svg defs.
+const GRADIENT_ID: &str = "qr-dark-gradient";
+
+// Directions of outline edges, in clockwise order.
+const RIGHT: usize = 0;
+const DOWN: usize = 1;
+const LEFT: usize = 2;
+const UP: usize = 3;
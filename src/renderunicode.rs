@@ -0,0 +1,100 @@
+//! Renders the QR code to text with unicode block elements.
+//!
+//! Two module rows are packed into one line, so the modules are about square in a terminal.
+use crate::matrix::Matrix;
+use crate::qr::Qr;
+use crate::rendercommons::QuietZone;
+
+/// A string renderer for converting a QR code into unicode blocks,
+/// suitable for printing to a terminal.
+///
+/// By default the light modules are drawn as blocks, which scans in terminals
+/// with light text on a dark background.
+pub struct UnicodeRenderer {
+    dense: bool,
+    inverted: bool,
+    qz: QuietZone,
+}
+
+impl Default for UnicodeRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnicodeRenderer {
+    /// Create a new renderer.
+    pub fn new() -> Self {
+        Self {
+            dense: true,
+            inverted: false,
+            qz: QuietZone::qr(),
+        }
+    }
+
+    /// Set if two module rows are packed into one line with half blocks.
+    /// Otherwise each module is two full blocks wide and one line high,
+    /// for fonts where half blocks leave gaps.
+    pub fn dense(mut self, v: bool) -> Self {
+        self.dense = v;
+        self
+    }
+
+    /// Set if the dark modules are drawn as blocks instead of the light ones,
+    /// for terminals with dark text on a light background.
+    pub fn inverted(mut self, v: bool) -> Self {
+        self.inverted = v;
+        self
+    }
+
+    /// Set the quiet zone, as a module count or `true` for the 4 modules of QR codes.
+    pub fn quiet_zone(mut self, v: impl Into<QuietZone>) -> Self {
+        self.qz = v.into();
+        self
+    }
+
+    /// Render QR to string.
+    ///
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let qr = Qr::new("HELLO WORLD").unwrap();
+    /// let s = UnicodeRenderer::new().render(&qr);
+    /// // 21 modules and 4 on each side for the quiet zone.
+    /// assert_eq!(s.lines().count(), 15);
+    /// assert!(s.lines().all(|line| line.chars().count() == 29));
+    /// ```
+    pub fn render(&self, qr: &Qr) -> String {
+        self.render_matrix(&qr.matrix)
+    }
+
+    /// Render matrix to string.
+    pub fn render_matrix(&self, matrix: &Matrix) -> String {
+        let w = self.qz.width(matrix);
+        let h = self.qz.height(matrix);
+        let mut res = String::with_capacity(w * h);
+        let step = if self.dense { 2 } else { 1 };
+        for y in (0..h).step_by(step) {
+            for x in 0..w {
+                let top = self.is_block(matrix, x, y);
+                if self.dense {
+                    let bottom = y + 1 < h && self.is_block(matrix, x, y + 1);
+                    res.push(match (top, bottom) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    });
+                } else {
+                    res.push_str(if top { "██" } else { "  " });
+                }
+            }
+            res.push('\n');
+        }
+        res
+    }
+
+    // If the module at x and y, counted with the quiet zone, is drawn as a block.
+    fn is_block(&self, matrix: &Matrix, x: usize, y: usize) -> bool {
+        self.qz.is_dark(matrix, x, y) == self.inverted
+    }
+}
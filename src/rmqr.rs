@@ -0,0 +1,482 @@
+//! Rectangular Micro QR codes, rMQR, from R7x43 to R17x139.
+//!
+//! Fits narrow spaces where a square QR code would waste space.
+//! There's a finder pattern to the left and a sub-finder pattern in the bottom right corner,
+//! with alignment patterns in columns between them.
+//! Each rMQR code holds a single segment and is always masked with the same mask.
+
+use crate::builder::{Error, ZigZagIt};
+use crate::data;
+use crate::ec::{self, ECLevel};
+use crate::info;
+use crate::mask::{self, Mask};
+use crate::matrix::{Matrix, Module};
+use crate::mode::Mode;
+use crate::rendercommons;
+
+use bitvec::prelude::*;
+
+/// rMQR code version, the version indicator from 0 for R7x43 to 31 for R17x139.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RmqrVersion(pub usize);
+
+impl RmqrVersion {
+    /// Create a new version, must be in the [0..31] range.
+    pub fn new(v: usize) -> RmqrVersion {
+        assert!(v < 32);
+        RmqrVersion(v)
+    }
+
+    /// Returns the version with the given width and height, if there is one.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// assert_eq!(RmqrVersion::from_size(43, 7), Some(RmqrVersion::new(0)));
+    /// assert_eq!(RmqrVersion::from_size(43, 8), None);
+    /// ```
+    pub fn from_size(width: usize, height: usize) -> Option<RmqrVersion> {
+        SIZES.iter().position(|x| *x == (width, height)).map(RmqrVersion)
+    }
+
+    /// Returns the width of the rMQR code.
+    pub fn width(&self) -> usize {
+        SIZES[self.0].0
+    }
+
+    /// Returns the height of the rMQR code.
+    pub fn height(&self) -> usize {
+        SIZES[self.0].1
+    }
+
+    /// Returns the required len of the char count bit representation.
+    pub fn char_count_len(&self, mode: Mode) -> usize {
+        let i = match mode {
+            Mode::Numeric => 0,
+            Mode::Alphanumeric => 1,
+            Mode::Byte => 2,
+            Mode::Kanji => 3,
+        };
+        CHAR_COUNT_LENS[self.0][i]
+    }
+
+    // Center columns of the alignment patterns.
+    fn alignment_columns(&self) -> &'static [usize] {
+        match self.width() {
+            27 => &[],
+            43 => &[21],
+            59 => &[19, 39],
+            77 => &[25, 51],
+            99 => &[23, 49, 75],
+            139 => &[27, 55, 83, 111],
+            w => panic!("Malformed rMQR width {}", w),
+        }
+    }
+}
+
+/// The rMQR code.
+///
+/// Encapsulates a matrix, the 2D-grid containing the modules
+/// and some information about the rMQR code.
+/// Render the matrix with the render_matrix method of a renderer,
+/// with dimensions in proportion to the width and height of the matrix.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rmqr {
+    /// Version of the rMQR code.
+    pub version: RmqrVersion,
+
+    /// Error correction level, M or H.
+    pub ecl: ECLevel,
+
+    /// Encoding mode.
+    pub mode: Mode,
+
+    /// The modules.
+    pub matrix: Matrix,
+}
+
+impl Rmqr {
+    /// Create a new rMQR code from a string.
+    /// The version with the smallest area that can hold the string is used.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let code = Rmqr::new("https://bestia.dev").unwrap();
+    /// assert_eq!((code.width(), code.height()), (43, 11));
+    /// ```
+    pub fn new(s: &str) -> Result<Rmqr, Error> {
+        RmqrBuilder::new().ecl(ECLevel::M).into(s)
+    }
+
+    /// Returns the width of the rMQR code.
+    pub fn width(&self) -> usize {
+        self.version.width()
+    }
+
+    /// Returns the height of the rMQR code.
+    pub fn height(&self) -> usize {
+        self.version.height()
+    }
+}
+
+/// Builder for an rMQR code.
+pub struct RmqrBuilder {
+    /// Version to use.
+    /// If not set the version with the smallest area will be calculated.
+    pub version: Option<RmqrVersion>,
+    /// Height to use when calculating the version, one of 7, 9, 11, 13, 15 and 17.
+    /// If not set all heights are considered.
+    pub height: Option<usize>,
+    /// Error correction level to use, only ECLevel::M and ECLevel::H are supported.
+    /// If not set ECLevel::M will be used.
+    pub ecl: ECLevel,
+    /// Encoding mode to use.
+    /// If not set it will be calculated from the input data.
+    pub mode: Option<Mode>,
+
+    /// Resulting matrix.
+    ///
+    /// Note that even though the matrix is not an Option it might still be invalid.
+    /// This to simplify the implementation.
+    pub matrix: Matrix,
+}
+
+impl Default for RmqrBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RmqrBuilder {
+    /// Create a new builder.
+    pub fn new() -> RmqrBuilder {
+        RmqrBuilder {
+            version: None,
+            height: None,
+            ecl: ECLevel::M,
+            mode: None,
+
+            matrix: Matrix::new(0),
+        }
+    }
+
+    /// Set version. If not set the version with the smallest area will be used.
+    pub fn version(mut self, v: RmqrVersion) -> Self {
+        // Override old tmp matrix.
+        self.matrix = Matrix::new_rect(v.width(), v.height());
+        self.version = Some(v);
+        self
+    }
+
+    /// Only consider versions with this height when calculating the version.
+    /// Useful when printing on strips of a fixed height.
+    pub fn height(mut self, height: usize) -> Self {
+        assert!(SIZES.iter().any(|x| x.1 == height));
+        self.height = Some(height);
+        self
+    }
+
+    /// Set error correction, ECLevel::M or ECLevel::H. Will default to ECLevel::M.
+    pub fn ecl(mut self, ecl: ECLevel) -> Self {
+        self.ecl = ecl;
+        self
+    }
+
+    /// Set the mode to use, will otherwise be calculated from input.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Build all elements and generate an rMQR code.
+    pub fn into(mut self, s: &str) -> Result<Rmqr, Error> {
+        self.add_all(s)?;
+        self.into_rmqr()
+    }
+
+    /// Convert the builder into an rMQR code.
+    pub fn into_rmqr(self) -> Result<Rmqr, Error> {
+        if !self.complete() {
+            return Err(Error::IncompleteBuilder);
+        }
+
+        Ok(Rmqr {
+            matrix: self.matrix,
+
+            version: self.version.unwrap(),
+            ecl: self.ecl,
+            mode: self.mode.unwrap(),
+        })
+    }
+
+    /// Add all elements of an rMQR code.
+    pub fn add_all(&mut self, s: &str) -> Result<(), Error> {
+        self.ensure_settings(s)?;
+        self.add_fun_patterns();
+        self.add_data(s)?;
+        self.mask_data();
+        self.add_format_info();
+
+        Ok(())
+    }
+
+    /// Add function patterns.
+    pub fn add_fun_patterns(&mut self) {
+        self.add_finder();
+        self.add_sub_finder();
+        self.add_corner_finders();
+        self.add_alignments();
+        self.add_timing_patterns();
+        self.add_reserved_areas();
+    }
+
+    /// Add data.
+    pub fn add_data(&mut self, s: &str) -> Result<(), Error> {
+        self.ensure_settings(s)?;
+
+        let version = self.version.unwrap();
+        let ecl = self.ecl;
+
+        let v = data::encode_rmqr(s, self.mode.unwrap(), version, ecl)?;
+        let v = ec::add_rmqr(v, version, ecl)?;
+        self.add_raw_data(&v);
+
+        Ok(())
+    }
+
+    /// Add raw data.
+    pub fn add_raw_data(&mut self, v: &BitVec<Msb0, u8>) {
+        let mut vi = 0;
+        for (x, y) in ZigZagIt::new_rmqr(self.matrix.width, self.matrix.height) {
+            if self.matrix.is_fun(x, y) {
+                continue;
+            }
+            self.matrix.set_data(x, y, v[vi]);
+            vi += 1;
+        }
+        assert_eq!(vi, v.len());
+    }
+
+    /// Mask data. rMQR codes always use mask 4.
+    pub fn mask_data(&mut self) {
+        self.matrix = mask::apply_mask(Mask::new(4), &self.matrix);
+    }
+
+    /// Add format info, next to both the finder and the sub-finder.
+    pub fn add_format_info(&mut self) {
+        // Hard assumption that we have necessary data.
+        let (finder, sub_finder) = info::rmqr_format_info(self.version.unwrap(), self.ecl).unwrap();
+        let w = self.matrix.width;
+        let h = self.matrix.height;
+
+        // The least significant bit is placed first, it's the last bit in the BitVec.
+        // 15 bits in three columns, then 3 more bits.
+        for (i, v) in finder.iter().rev().enumerate() {
+            if i < 15 {
+                self.matrix.set_fun(8 + i / 5, 1 + i % 5, *v);
+            } else {
+                self.matrix.set_fun(11, i - 14, *v);
+            }
+        }
+        for (i, v) in sub_finder.iter().rev().enumerate() {
+            if i < 15 {
+                self.matrix.set_fun(w - 8 + i / 5, h - 6 + i % 5, *v);
+            } else {
+                self.matrix.set_fun(w - 20 + i, h - 6, *v);
+            }
+        }
+    }
+
+    /// Convert to debug string.
+    pub fn to_dbg_string(&self) -> String {
+        rendercommons::to_dbg_string(&self.matrix)
+    }
+
+    // Return true if the build is complete.
+    fn complete(&self) -> bool {
+        self.mode.is_some() && self.version.is_some() && self.matrix.complete()
+    }
+
+    // Ensure we have required settings, otherwise decide from the data.
+    fn ensure_settings(&mut self, s: &str) -> Result<(), Error> {
+        let mode = *self.mode.get_or_insert_with(|| Mode::from_str(s));
+        if self.version.is_none() {
+            // The version with the smallest area that fits the data.
+            let mut versions: Vec<RmqrVersion> = (0..SIZES.len())
+                .map(RmqrVersion::new)
+                .filter(|v| self.height.is_none() || self.height == Some(v.height()))
+                .collect();
+            versions.sort_by_key(|v| v.width() * v.height());
+            let mut res = Err(Error::UnsupportedMode);
+            for v in versions {
+                res = data::encode_rmqr(s, mode, v, self.ecl).map(|_| v);
+                if res.is_ok() {
+                    break;
+                }
+            }
+            self.version = Some(res?);
+        }
+
+        // Ensure the matrix is initialized.
+        let v = self.version.unwrap();
+        if self.matrix.width != v.width() || self.matrix.height != v.height() {
+            self.matrix = Matrix::new_rect(v.width(), v.height());
+        }
+        Ok(())
+    }
+
+    fn add_finder(&mut self) {
+        self.matrix.set_square(0, 0, 7, Module::Function(true));
+        self.matrix.set_square_outline(1, 1, 5, Module::Function(false));
+        // Separator to the right, and below unless it's the full height.
+        let h = self.matrix.height;
+        self.matrix.set_rect(7, 0, 7, h.min(8) - 1, Module::Function(false));
+        if h > 7 {
+            self.matrix.set_rect(0, 7, 7, 7, Module::Function(false));
+        }
+    }
+
+    // The sub-finder in the bottom right corner looks like a QR alignment pattern.
+    fn add_sub_finder(&mut self) {
+        let x = self.matrix.width - 5;
+        let y = self.matrix.height - 5;
+        self.matrix.set_square(x, y, 5, Module::Function(true));
+        self.matrix.set_square_outline(x + 1, y + 1, 3, Module::Function(false));
+    }
+
+    // Corner finders in the bottom left and top right corners.
+    fn add_corner_finders(&mut self) {
+        let w = self.matrix.width;
+        let h = self.matrix.height;
+
+        // The finder covers the bottom left corner of R7.
+        if h > 7 {
+            self.matrix.set_rect(0, h - 1, 2, h - 1, Module::Function(true));
+        }
+        // Below the separator of R9 there's only room for a single row.
+        if h > 9 {
+            self.matrix.set(0, h - 2, Module::Function(true));
+            self.matrix.set(1, h - 2, Module::Function(false));
+        }
+
+        self.matrix.set_rect(w - 2, 0, w - 1, 0, Module::Function(true));
+        self.matrix.set(w - 1, 1, Module::Function(true));
+        self.matrix.set(w - 2, 1, Module::Function(false));
+    }
+
+    // Alignment patterns at the top and bottom of each alignment column.
+    fn add_alignments(&mut self) {
+        let h = self.matrix.height;
+        for cx in self.version.unwrap().alignment_columns() {
+            for y in [0, h - 3].iter() {
+                self.matrix.set_square(cx - 1, *y, 3, Module::Function(true));
+                self.matrix.set(*cx, y + 1, Module::Function(false));
+            }
+        }
+    }
+
+    // Timing patterns along the top and bottom edges, the left and right edges
+    // and in the alignment columns. Fills the remaining modules of those rows and columns.
+    fn add_timing_patterns(&mut self) {
+        let w = self.matrix.width;
+        let h = self.matrix.height;
+        for x in 0..w {
+            self.set_timing(x, 0, x % 2 == 0);
+            self.set_timing(x, h - 1, x % 2 == 0);
+        }
+        let alignments = self.version.unwrap().alignment_columns();
+        for x in [0, w - 1].iter().chain(alignments.iter()) {
+            for y in 0..h {
+                self.set_timing(*x, y, y % 2 == 0);
+            }
+        }
+    }
+
+    fn set_timing(&mut self, x: usize, y: usize, v: bool) {
+        if *self.matrix.get(x, y) == Module::Unknown {
+            self.matrix.set(x, y, Module::Function(v));
+        }
+    }
+
+    fn add_reserved_areas(&mut self) {
+        let w = self.matrix.width;
+        let h = self.matrix.height;
+
+        // To the right of the finder.
+        self.matrix.set_rect(8, 1, 10, 5, Module::Reserved);
+        self.matrix.set_rect(11, 1, 11, 3, Module::Reserved);
+
+        // To the left of and above the sub-finder.
+        self.matrix.set_rect(w - 8, h - 6, w - 6, h - 2, Module::Reserved);
+        self.matrix.set_rect(w - 5, h - 6, w - 3, h - 6, Module::Reserved);
+    }
+}
+
+// Width and height of each version.
+static SIZES: [(usize, usize); 32] = [
+    (43, 7),
+    (59, 7),
+    (77, 7),
+    (99, 7),
+    (139, 7),
+    (43, 9),
+    (59, 9),
+    (77, 9),
+    (99, 9),
+    (139, 9),
+    (27, 11),
+    (43, 11),
+    (59, 11),
+    (77, 11),
+    (99, 11),
+    (139, 11),
+    (27, 13),
+    (43, 13),
+    (59, 13),
+    (77, 13),
+    (99, 13),
+    (139, 13),
+    (43, 15),
+    (59, 15),
+    (77, 15),
+    (99, 15),
+    (139, 15),
+    (43, 17),
+    (59, 17),
+    (77, 17),
+    (99, 17),
+    (139, 17),
+];
+
+// Char count lens for each version, ordered numeric, alphanumeric, byte, kanji.
+static CHAR_COUNT_LENS: [[usize; 4]; 32] = [
+    [4, 3, 3, 2], // R7x43
+    [5, 5, 4, 3], // R7x59
+    [6, 5, 5, 4], // R7x77
+    [7, 6, 5, 5], // R7x99
+    [7, 6, 6, 5], // R7x139
+    [5, 5, 4, 3], // R9x43
+    [6, 5, 5, 4], // R9x59
+    [7, 6, 5, 5], // R9x77
+    [7, 6, 6, 5], // R9x99
+    [8, 7, 6, 6], // R9x139
+    [4, 4, 3, 2], // R11x27
+    [6, 5, 5, 4], // R11x43
+    [7, 6, 5, 5], // R11x59
+    [7, 6, 6, 5], // R11x77
+    [8, 7, 6, 6], // R11x99
+    [8, 7, 7, 6], // R11x139
+    [5, 5, 4, 3], // R13x27
+    [6, 6, 5, 5], // R13x43
+    [7, 6, 6, 5], // R13x59
+    [7, 7, 6, 6], // R13x77
+    [8, 7, 7, 6], // R13x99
+    [8, 8, 7, 7], // R13x139
+    [7, 6, 6, 5], // R15x43
+    [7, 7, 6, 5], // R15x59
+    [8, 7, 7, 6], // R15x77
+    [8, 7, 7, 6], // R15x99
+    [9, 8, 7, 7], // R15x139
+    [7, 6, 6, 5], // R17x43
+    [8, 7, 6, 6], // R17x59
+    [8, 7, 7, 6], // R17x77
+    [8, 8, 7, 6], // R17x99
+    [9, 8, 8, 7], // R17x139
+];
@@ -0,0 +1,651 @@
+//! Scanning, locate and read a QR code in a grayscale image.
+//!
+//! The image is binarized with a local threshold, the three finder patterns are located
+//! by their 1:1:3:1:1 ratio and the bottom right alignment pattern is used
+//! to estimate the perspective. The modules are sampled into a matrix and decoded.
+//! It's all plain Rust without dependencies, so it works offline and in wasm.
+
+use crate::decode::{self, DecodeError, Decoded};
+use crate::info;
+use crate::matrix::{Matrix, Module};
+
+/// Scan a grayscale image for a QR code and decode it.
+///
+/// The image has one byte per pixel, 0 is black and 255 white, row by row.
+/// Fails with `DecodeError::InvalidImage` if its len isn't width * height.
+/// Dark modules on a light background are tried first, then inverted.
+/// ```
+/// # use qrcode53bytes::*;
+/// let qr = Qr::new("https://bestia.dev").unwrap();
+/// // Draw the QR code 4 pixels per module, with a 4 module quiet zone.
+/// let width = (qr.size() + 8) * 4;
+/// let mut image = vec![255; width * width];
+/// for y in 0..width {
+///     for x in 0..width {
+///         let (mx, my) = ((x / 4).wrapping_sub(4), (y / 4).wrapping_sub(4));
+///         if mx < qr.size() && my < qr.size() && qr.matrix.is_dark(mx, my) {
+///             image[y * width + x] = 0;
+///         }
+///     }
+/// }
+/// let decoded = scan::scan(&image, width, width).unwrap();
+/// assert_eq!(decoded.data(), "https://bestia.dev");
+/// ```
+pub fn scan(image: &[u8], width: usize, height: usize) -> Result<Decoded, DecodeError> {
+    if width.checked_mul(height) != Some(image.len()) {
+        return Err(DecodeError::InvalidImage);
+    }
+    let bitmap = Bitmap::binarize(image, width, height);
+    scan_bitmap(&bitmap).or_else(|e| scan_bitmap(&bitmap.inverted()).map_err(|_| e))
+}
+
+fn scan_bitmap(bitmap: &Bitmap) -> Result<Decoded, DecodeError> {
+    let mut finders = find_finders(bitmap);
+    // Finders found in many rows are more likely to be real.
+    finders.sort_by_key(|x| std::cmp::Reverse(x.count));
+    finders.truncate(MAX_FINDERS);
+
+    let mut triples = Vec::new();
+    for i in 0..finders.len() {
+        for j in i + 1..finders.len() {
+            for k in j + 1..finders.len() {
+                if let Some(x) = Triple::new(&finders[i], &finders[j], &finders[k]) {
+                    triples.push(x);
+                }
+            }
+        }
+    }
+    triples.sort_by(|a, b| a.score.total_cmp(&b.score));
+
+    let mut res = Err(DecodeError::NotFound);
+    for triple in triples.iter() {
+        res = decode_triple(bitmap, triple);
+        if res.is_ok() {
+            break;
+        }
+    }
+    res
+}
+
+// Sample and decode the QR code at three finder patterns.
+// The size is estimated from the distance between the finders,
+// for larger versions it's corrected with the version info.
+fn decode_triple(bitmap: &Bitmap, triple: &Triple) -> Result<Decoded, DecodeError> {
+    let estimate = triple.dimension(bitmap);
+    let mut sizes = vec![estimate, estimate + 4, estimate.saturating_sub(4)];
+    let mut res = Err(DecodeError::NotFound);
+    let mut i = 0;
+    while i < sizes.len() {
+        let size = sizes[i];
+        i += 1;
+        if !(21..=177).contains(&size) {
+            continue;
+        }
+        // Without the alignment pattern if a false one was found.
+        let alignment = triple.find_alignment(bitmap, size);
+        for a in [alignment, None].iter().take(if alignment.is_some() { 2 } else { 1 }) {
+            let matrix = match sample(bitmap, triple, size, *a) {
+                Some(x) => x,
+                None => continue,
+            };
+            if size >= 45 {
+                if let Some(v) = read_version_info(&matrix) {
+                    if !sizes.contains(&v.size()) {
+                        sizes.insert(i, v.size());
+                    }
+                }
+            }
+            res = decode::decode_matrix(&matrix);
+            if res.is_ok() {
+                return res;
+            }
+        }
+    }
+    res
+}
+
+// Sample the modules of a QR code of a certain size.
+// The bottom right alignment pattern catches the perspective,
+// without it the QR code is assumed to be a parallelogram.
+// Returns None if the QR code would be partly outside the image.
+fn sample(bitmap: &Bitmap, triple: &Triple, size: usize, alignment: Option<Point>) -> Option<Matrix> {
+    let s = size as f64;
+    let (tl, tr, bl) = (triple.top_left, triple.top_right, triple.bottom_left);
+    let (fourth_src, fourth_dst) = match alignment {
+        Some(p) => (Point { x: s - 6.5, y: s - 6.5 }, p),
+        None => (Point { x: s - 3.5, y: s - 3.5 }, triple.bottom_right()),
+    };
+    let src = [
+        Point { x: 3.5, y: 3.5 },
+        Point { x: s - 3.5, y: 3.5 },
+        Point { x: 3.5, y: s - 3.5 },
+        fourth_src,
+    ];
+    let transform = Transform::new(&src, &[tl, tr, bl, fourth_dst])?;
+
+    let mut matrix = Matrix::new(size);
+    for y in 0..size {
+        for x in 0..size {
+            let p = transform.map(Point {
+                x: x as f64 + 0.5,
+                y: y as f64 + 0.5,
+            });
+            // Allow a little rounding error along the edges.
+            if p.x < -1.0 || p.y < -1.0 || p.x > bitmap.width as f64 + 1.0 || p.y > bitmap.height as f64 + 1.0 {
+                return None;
+            }
+            let px = (p.x.max(0.0) as usize).min(bitmap.width - 1);
+            let py = (p.y.max(0.0) as usize).min(bitmap.height - 1);
+            matrix.set(x, y, Module::Data(bitmap.is_dark(px, py)));
+        }
+    }
+    Some(matrix)
+}
+
+// Read the version info, from the second copy if the first has too many errors.
+fn read_version_info(matrix: &Matrix) -> Option<crate::version::Version> {
+    let size = matrix.width;
+    // The least significant bit is placed first.
+    let read = |transposed: bool| {
+        (0..18).fold(0u32, |acc, i| {
+            let (a, b) = (i / 3, size - 11 + i % 3);
+            let (x, y) = if transposed { (b, a) } else { (a, b) };
+            acc | (matrix.is_dark(x, y) as u32) << i
+        })
+    };
+    info::decode_version_info(read(false)).or_else(|| info::decode_version_info(read(true)))
+}
+
+// A point in the image, pixel x,y covers x..x+1 and y..y+1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+impl Point {
+    fn distance(&self, other: &Point) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+    }
+}
+
+// A binarized image, true is dark.
+struct Bitmap {
+    width: usize,
+    height: usize,
+    bits: Vec<bool>,
+}
+
+impl Bitmap {
+    // The threshold for each block of pixels is the average of the surrounding blocks.
+    // Blocks with low contrast are assumed to be light, unless their neighbours are darker.
+    fn binarize(image: &[u8], width: usize, height: usize) -> Bitmap {
+        let bw = width.div_ceil(BLOCK_SIZE);
+        let bh = height.div_ceil(BLOCK_SIZE);
+
+        let mut averages = vec![0u32; bw * bh];
+        for by in 0..bh {
+            for bx in 0..bw {
+                let (mut min, mut max, mut sum, mut count) = (255u32, 0u32, 0u32, 0u32);
+                for y in by * BLOCK_SIZE..((by + 1) * BLOCK_SIZE).min(height) {
+                    for x in bx * BLOCK_SIZE..((bx + 1) * BLOCK_SIZE).min(width) {
+                        let v = image[y * width + x] as u32;
+                        min = min.min(v);
+                        max = max.max(v);
+                        sum += v;
+                        count += 1;
+                    }
+                }
+                let mut average = sum / count;
+                if max - min <= MIN_DYNAMIC_RANGE {
+                    average = min / 2;
+                    if by > 0 && bx > 0 {
+                        let neighbours =
+                            (averages[(by - 1) * bw + bx] + 2 * averages[by * bw + bx - 1] + averages[(by - 1) * bw + bx - 1]) / 4;
+                        if min < neighbours {
+                            average = neighbours;
+                        }
+                    }
+                }
+                averages[by * bw + bx] = average;
+            }
+        }
+
+        let mut bits = vec![false; width * height];
+        for by in 0..bh {
+            for bx in 0..bw {
+                let (mut sum, mut count) = (0, 0);
+                for y in by.saturating_sub(2)..(by + 3).min(bh) {
+                    for x in bx.saturating_sub(2)..(bx + 3).min(bw) {
+                        sum += averages[y * bw + x];
+                        count += 1;
+                    }
+                }
+                let threshold = sum / count;
+                for y in by * BLOCK_SIZE..((by + 1) * BLOCK_SIZE).min(height) {
+                    for x in bx * BLOCK_SIZE..((bx + 1) * BLOCK_SIZE).min(width) {
+                        bits[y * width + x] = (image[y * width + x] as u32) <= threshold;
+                    }
+                }
+            }
+        }
+        Bitmap { width, height, bits }
+    }
+
+    fn inverted(&self) -> Bitmap {
+        Bitmap {
+            width: self.width,
+            height: self.height,
+            bits: self.bits.iter().map(|x| !x).collect(),
+        }
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.bits[y * self.width + x]
+    }
+
+    // None if outside the image.
+    fn get(&self, x: isize, y: isize) -> Option<bool> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(self.is_dark(x as usize, y as usize))
+    }
+
+    // Runs of pixels with the same color in a part of a row, as start, len and dark.
+    fn runs(&self, y: usize, x0: usize, x1: usize) -> Vec<(usize, usize, bool)> {
+        let mut runs: Vec<(usize, usize, bool)> = Vec::new();
+        for x in x0..x1 {
+            let dark = self.is_dark(x, y);
+            match runs.last_mut() {
+                Some(run) if run.2 == dark => run.1 += 1,
+                _ => runs.push((x, 1, dark)),
+            }
+        }
+        runs
+    }
+}
+
+// A located finder pattern.
+#[derive(Debug, Clone, Copy)]
+struct Finder {
+    center: Point,
+    // Estimated module size in pixels.
+    module: f64,
+    // Number of rows it was found in.
+    count: usize,
+}
+
+// Scan all rows for the 1:1:3:1:1 ratio, then check it vertically and horizontally through the center.
+fn find_finders(bitmap: &Bitmap) -> Vec<Finder> {
+    let mut finders: Vec<Finder> = Vec::new();
+    for y in 0..bitmap.height {
+        let runs = bitmap.runs(y, 0, bitmap.width);
+        for w in runs.windows(5) {
+            let counts = [w[0].1, w[1].1, w[2].1, w[3].1, w[4].1];
+            if !w[0].2 || !finder_ratio(&counts) {
+                continue;
+            }
+            let total: usize = counts.iter().sum();
+            let cx = w[2].0 + w[2].1 / 2;
+            let (cy, v_total) = match cross_check(bitmap, cx, y, 0, 1, total, finder_ratio) {
+                Some((offset, n)) => (y as f64 + offset, n),
+                None => continue,
+            };
+            // Finders are square, the heights shouldn't differ much.
+            if (v_total as f64 - total as f64).abs() > 0.4 * total as f64 {
+                continue;
+            }
+            let (cx, h_total) = match cross_check(bitmap, cx, cy as usize, 1, 0, total, finder_ratio) {
+                Some((offset, n)) => (cx as f64 + offset, n),
+                None => continue,
+            };
+            let finder = Finder {
+                center: Point { x: cx, y: cy },
+                module: (h_total + v_total) as f64 / 14.0,
+                count: 1,
+            };
+            add_finder(&mut finders, finder);
+        }
+    }
+    finders
+}
+
+// Merge with an existing finder if they're at about the same place with about the same size.
+fn add_finder(finders: &mut Vec<Finder>, finder: Finder) {
+    let same = finders.iter_mut().find(|x| {
+        (x.center.x - finder.center.x).abs() <= x.module
+            && (x.center.y - finder.center.y).abs() <= x.module
+            && (x.module - finder.module).abs() <= x.module / 2.0
+    });
+    match same {
+        Some(x) => {
+            let n = x.count as f64;
+            x.center.x = (x.center.x * n + finder.center.x) / (n + 1.0);
+            x.center.y = (x.center.y * n + finder.center.y) / (n + 1.0);
+            x.module = (x.module * n + finder.module) / (n + 1.0);
+            x.count += 1;
+        }
+        None => finders.push(finder),
+    }
+}
+
+// Dark, light, dark, light and dark runs in the ratio 1:1:3:1:1.
+fn finder_ratio(counts: &[usize; 5]) -> bool {
+    let total: usize = counts.iter().sum();
+    if total < 7 || counts.contains(&0) {
+        return false;
+    }
+    let module = total as f64 / 7.0;
+    let variance = module / 2.0;
+    counts
+        .iter()
+        .zip([1.0, 1.0, 3.0, 1.0, 1.0].iter())
+        .all(|(c, r)| (*c as f64 - module * r).abs() < variance * r)
+}
+
+// The middle three runs of an alignment pattern in the ratio 1:1:1,
+// the outer runs are the dark ring, merged with any surrounding dark modules.
+// Runs are longer than the module size when the QR code is rotated, so it's only a rough limit.
+fn alignment_ratio(counts: &[usize; 5], module: f64) -> bool {
+    let run = counts[1..4].iter().sum::<usize>() as f64 / 3.0;
+    counts.iter().all(|x| *x > 0)
+        && counts[1..4].iter().all(|x| (*x as f64 - run).abs() < run / 2.0)
+        && run > module / 2.0
+        && run < module * 2.0
+        && counts[0] as f64 >= run / 2.0
+        && counts[4] as f64 >= run / 2.0
+}
+
+// Modules that differ from an alignment pattern, a dark ring, a light ring and a dark center.
+fn alignment_errors(bitmap: &Bitmap, center: &Point, across: &Point, down: &Point) -> usize {
+    let mut errors = 0;
+    for j in -2i32..=2 {
+        for i in -2i32..=2 {
+            let x = center.x + i as f64 * across.x + j as f64 * down.x;
+            let y = center.y + i as f64 * across.y + j as f64 * down.y;
+            let expected = i.abs().max(j.abs()) != 1;
+            if bitmap.get(x.floor() as isize, y.floor() as isize) != Some(expected) {
+                errors += 1;
+            }
+        }
+    }
+    errors
+}
+
+// Count the 5 runs of a pattern through a dark center, along a line in the x,y direction.
+// Returns the center offset from the start along the line and the total len.
+fn cross_check(
+    bitmap: &Bitmap,
+    x: usize,
+    y: usize,
+    dx: isize,
+    dy: isize,
+    max_count: usize,
+    ratio: impl Fn(&[usize; 5]) -> bool,
+) -> Option<(f64, usize)> {
+    let dark = |t: isize| bitmap.get(x as isize + t * dx, y as isize + t * dy);
+    let mut counts = [0usize; 5];
+
+    // Backwards from the center, then forwards.
+    let mut t = 0;
+    for (i, v) in [(2, true), (1, false), (0, true)].iter() {
+        while dark(t) == Some(*v) && counts[*i] <= max_count {
+            counts[*i] += 1;
+            t -= 1;
+        }
+    }
+    t = 1;
+    for (i, v) in [(2, true), (3, false), (4, true)].iter() {
+        while dark(t) == Some(*v) && counts[*i] <= max_count {
+            counts[*i] += 1;
+            t += 1;
+        }
+    }
+    if !ratio(&counts) {
+        return None;
+    }
+    let center = t as f64 - counts[4] as f64 - counts[3] as f64 - counts[2] as f64 / 2.0;
+    Some((center, counts.iter().sum()))
+}
+
+// Three finders ordered as the top left, top right and bottom left corners.
+struct Triple {
+    top_left: Point,
+    top_right: Point,
+    bottom_left: Point,
+    module: f64,
+    // How far from a right isosceles triangle, lower is better.
+    score: f64,
+}
+
+impl Triple {
+    fn new(a: &Finder, b: &Finder, c: &Finder) -> Option<Triple> {
+        let modules = [a.module, b.module, c.module];
+        let min = modules.iter().cloned().fold(f64::MAX, f64::min);
+        let max = modules.iter().cloned().fold(0.0, f64::max);
+        if max > 2.0 * min {
+            return None;
+        }
+
+        // The top left corner is opposite of the longest side.
+        let (ab, bc, ac) = (
+            a.center.distance(&b.center),
+            b.center.distance(&c.center),
+            a.center.distance(&c.center),
+        );
+        let (tl, p, q, hyp) = if bc >= ab && bc >= ac {
+            (a, b, c, bc)
+        } else if ac >= ab {
+            (b, a, c, ac)
+        } else {
+            (c, a, b, ab)
+        };
+        let (leg1, leg2) = (tl.center.distance(&p.center), tl.center.distance(&q.center));
+        let score = (leg1 - leg2).abs() / leg1.max(leg2) + (hyp.powi(2) - leg1.powi(2) - leg2.powi(2)).abs() / hyp.powi(2);
+        if score > 0.5 {
+            return None;
+        }
+
+        // Clockwise order, with y pointing down.
+        let cross = (p.center.x - tl.center.x) * (q.center.y - tl.center.y) - (p.center.y - tl.center.y) * (q.center.x - tl.center.x);
+        let (tr, bl) = if cross > 0.0 { (p, q) } else { (q, p) };
+        Some(Triple {
+            top_left: tl.center,
+            top_right: tr.center,
+            bottom_left: bl.center,
+            module: (a.module + b.module + c.module) / 3.0,
+            score,
+        })
+    }
+
+    // The bottom right corner, if there's no perspective.
+    fn bottom_right(&self) -> Point {
+        Point {
+            x: self.top_right.x - self.top_left.x + self.bottom_left.x,
+            y: self.top_right.y - self.top_left.y + self.bottom_left.y,
+        }
+    }
+
+    // Search for the bottom right alignment pattern around its estimated center,
+    // in larger and larger areas. It's 3 modules closer to the top left finder than the other finders.
+    // Candidates are verified by sampling the 5x5 modules, the one closest to the estimate is used.
+    fn find_alignment(&self, bitmap: &Bitmap, size: usize) -> Option<Point> {
+        if size <= 21 {
+            return None;
+        }
+        let (tl, tr, bl) = (&self.top_left, &self.top_right, &self.bottom_left);
+        let s = size as f64 - 7.0;
+        let br = self.bottom_right();
+        let estimate = Point {
+            x: tl.x + (1.0 - 3.0 / s) * (br.x - tl.x),
+            y: tl.y + (1.0 - 3.0 / s) * (br.y - tl.y),
+        };
+        // One module to the right and one module down.
+        let across = Point {
+            x: (tr.x - tl.x) / s,
+            y: (tr.y - tl.y) / s,
+        };
+        let down = Point {
+            x: (bl.x - tl.x) / s,
+            y: (bl.y - tl.y) / s,
+        };
+        let module = (across.distance(&Point { x: 0.0, y: 0.0 }) + down.distance(&Point { x: 0.0, y: 0.0 })) / 2.0;
+
+        let ratio = |x: &[usize; 5]| alignment_ratio(x, module);
+        for allowance in [4.0, 8.0, 16.0].iter() {
+            let r = allowance * module;
+            let x0 = (estimate.x - r).max(0.0) as usize;
+            let x1 = ((estimate.x + r).max(0.0) as usize).min(bitmap.width);
+            let y0 = (estimate.y - r).max(0.0) as usize;
+            let y1 = ((estimate.y + r).max(0.0) as usize).min(bitmap.height);
+
+            let mut best: Option<(usize, f64, Point)> = None;
+            for y in y0..y1 {
+                let runs = bitmap.runs(y, x0, x1);
+                for w in runs.windows(5) {
+                    let counts = [w[0].1, w[1].1, w[2].1, w[3].1, w[4].1];
+                    if !w[0].2 || !ratio(&counts) {
+                        continue;
+                    }
+                    let cx = w[2].0 + w[2].1 / 2;
+                    let max_count = (2.0 * module) as usize + 1;
+                    let cy = match cross_check(bitmap, cx, y, 0, 1, max_count, ratio) {
+                        Some((offset, _)) => y as f64 + offset,
+                        None => continue,
+                    };
+                    let p = Point {
+                        x: w[2].0 as f64 + w[2].1 as f64 / 2.0,
+                        y: cy,
+                    };
+                    let errors = alignment_errors(bitmap, &p, &across, &down);
+                    let candidate = (errors, p.distance(&estimate), p);
+                    let better = match best {
+                        Some(b) => (candidate.0, candidate.1) < (b.0, b.1),
+                        None => true,
+                    };
+                    if errors <= 2 && better {
+                        best = Some(candidate);
+                    }
+                }
+            }
+            if let Some(b) = best {
+                return Some(b.2);
+            }
+        }
+        None
+    }
+
+    // Estimate the size from the distance between the finders, it's 7 modules more.
+    // Rounded to the closest valid size.
+    fn dimension(&self, bitmap: &Bitmap) -> usize {
+        let (tl, tr, bl) = (&self.top_left, &self.top_right, &self.bottom_left);
+        let across = tl.distance(tr) / self.module_between(bitmap, tl, tr);
+        let down = tl.distance(bl) / self.module_between(bitmap, tl, bl);
+        let size = ((across + down) / 2.0 + 7.0).round() as usize;
+        let v = (size.saturating_sub(17) as f64 / 4.0).round() as usize;
+        v.clamp(1, 40) * 4 + 17
+    }
+
+    // The module size along the line between two finders.
+    // The module size of the finders is measured along rows, that's too large when rotated.
+    fn module_between(&self, bitmap: &Bitmap, a: &Point, b: &Point) -> f64 {
+        match (finder_width(bitmap, a, b), finder_width(bitmap, b, a)) {
+            (Some(x), Some(y)) => (x + y) / 14.0,
+            _ => self.module,
+        }
+    }
+}
+
+// The width of a finder along the line towards another point, 7 modules.
+// From the center both ways, through the dark center, the light ring and the dark ring.
+fn finder_width(bitmap: &Bitmap, center: &Point, towards: &Point) -> Option<f64> {
+    let (dx, dy) = (towards.x - center.x, towards.y - center.y);
+    let len = dx.abs().max(dy.abs());
+    if len == 0.0 {
+        return None;
+    }
+    // Move a pixel at a time along the major axis.
+    let (sx, sy) = (dx / len, dy / len);
+    let step = (sx * sx + sy * sy).sqrt();
+
+    let mut width = 0.0;
+    for dir in [1.0, -1.0].iter() {
+        let mut transitions = 0;
+        let mut last = true;
+        let mut i = 0;
+        while transitions < 3 {
+            i += 1;
+            let x = center.x + dir * sx * i as f64;
+            let y = center.y + dir * sy * i as f64;
+            let dark = bitmap.get(x.floor() as isize, y.floor() as isize)?;
+            if dark != last {
+                transitions += 1;
+                last = dark;
+            }
+            if i as f64 > len {
+                return None;
+            }
+        }
+        width += i as f64 * step;
+    }
+    Some(width)
+}
+
+// Perspective transform from module coordinates to image coordinates.
+struct Transform([f64; 8]);
+
+impl Transform {
+    // Solve the 8 unknowns from four point pairs.
+    // x' = (a x + b y + c) / (g x + h y + 1) and y' = (d x + e y + f) / (g x + h y + 1)
+    fn new(src: &[Point; 4], dst: &[Point; 4]) -> Option<Transform> {
+        let mut m = [[0.0f64; 9]; 8];
+        for (i, (s, d)) in src.iter().zip(dst.iter()).enumerate() {
+            m[2 * i] = [s.x, s.y, 1.0, 0.0, 0.0, 0.0, -s.x * d.x, -s.y * d.x, d.x];
+            m[2 * i + 1] = [0.0, 0.0, 0.0, s.x, s.y, 1.0, -s.x * d.y, -s.y * d.y, d.y];
+        }
+
+        // Gaussian elimination with partial pivoting.
+        for col in 0..8 {
+            let pivot = (col..8)
+                .max_by(|a, b| m[*a][col].abs().total_cmp(&m[*b][col].abs()))
+                .unwrap();
+            if m[pivot][col].abs() < 1e-9 {
+                return None;
+            }
+            m.swap(col, pivot);
+            let pivot_row = m[col];
+            for (i, row) in m.iter_mut().enumerate() {
+                if i != col {
+                    let f = row[col] / pivot_row[col];
+                    for (x, p) in row.iter_mut().zip(pivot_row.iter()).skip(col) {
+                        *x -= f * p;
+                    }
+                }
+            }
+        }
+        let mut res = [0.0; 8];
+        for (i, x) in res.iter_mut().enumerate() {
+            *x = m[i][8] / m[i][i];
+        }
+        Some(Transform(res))
+    }
+
+    fn map(&self, p: Point) -> Point {
+        let t = &self.0;
+        let w = t[6] * p.x + t[7] * p.y + 1.0;
+        Point {
+            x: (t[0] * p.x + t[1] * p.y + t[2]) / w,
+            y: (t[3] * p.x + t[4] * p.y + t[5]) / w,
+        }
+    }
+}
+
+// Size in pixels of the blocks used when binarizing.
+const BLOCK_SIZE: usize = 8;
+
+// Blocks with less difference between the darkest and lightest pixel have no contrast.
+const MIN_DYNAMIC_RANGE: u32 = 24;
+
+// Max finder candidates, the ones found in the most rows are kept.
+const MAX_FINDERS: usize = 8;
@@ -0,0 +1,209 @@
+//! Segments split the data into parts with different encoding modes.
+//!
+//! A string with both digits and other chars can often be made shorter
+//! by encoding the digits in numeric mode and the rest in another mode.
+
+use crate::mode::Mode;
+use crate::version::Version;
+
+/// ECI designator for UTF-8.
+pub const ECI_UTF8: u32 = 26;
+
+/// A part of the data, encoded with a single mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Encoding mode of the segment.
+    pub mode: Mode,
+    /// The data of the segment.
+    pub data: String,
+    /// ECI designator placed before the segment.
+    /// It stays in effect for the following segments, until another ECI designator.
+    pub eci: Option<u32>,
+}
+
+impl Segment {
+    /// Create a new segment.
+    pub fn new(mode: Mode, data: &str) -> Segment {
+        Segment {
+            mode,
+            data: data.to_string(),
+            eci: None,
+        }
+    }
+
+    /// Place an ECI designator before the segment, must be less than 1000000.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let segment = Segment::new(Mode::Byte, "Grüße, 世界").with_eci(segment::ECI_UTF8);
+    /// let qr = QrBuilder::new().into_segments(&[segment]);
+    /// ```
+    pub fn with_eci(mut self, eci: u32) -> Segment {
+        assert!(eci < 1_000_000);
+        self.eci = Some(eci);
+        self
+    }
+
+    /// Returns the char count, as stored in the QR code.
+    /// If the UTF-8 ECI is in effect byte mode data is UTF-8 encoded,
+    /// otherwise ISO-8859-1 is used when possible.
+    pub fn char_count(&self, utf8: bool) -> usize {
+        match self.mode {
+            Mode::Byte if utf8 => self.data.len(),
+            mode => mode.char_count(&self.data),
+        }
+    }
+
+    /// Returns the len in bits of the encoded segment.
+    /// Includes the ECI designator, the mode indicator and the char count.
+    pub fn bit_len(&self, v: Version) -> usize {
+        self.bit_len_with(v, self.eci == Some(ECI_UTF8))
+    }
+
+    fn bit_len_with(&self, v: Version, utf8: bool) -> usize {
+        let eci_len = match self.eci {
+            None => 0,
+            Some(0..=127) => 4 + 8,
+            Some(128..=16383) => 4 + 16,
+            Some(_) => 4 + 24,
+        };
+        let n = self.char_count(utf8);
+        let data_len = match self.mode {
+            Mode::Numeric => (n / 3) * 10 + [0, 4, 7][n % 3],
+            Mode::Alphanumeric => (n / 2) * 11 + (n % 2) * 6,
+            Mode::Kanji => n * 13,
+            Mode::Byte => n * 8,
+        };
+        eci_len + 4 + v.char_count_len(self.mode) + data_len
+    }
+}
+
+/// Returns the total len in bits of the encoded segments.
+pub fn bit_len(segments: &[Segment], v: Version) -> usize {
+    let mut utf8 = utf8_default(segments);
+    let mut res = 0;
+    for segment in segments.iter() {
+        if let Some(eci) = segment.eci {
+            utf8 = eci == ECI_UTF8;
+        }
+        res += segment.bit_len_with(v, utf8);
+    }
+    res
+}
+
+/// Returns true if byte mode data before any ECI designator is UTF-8 encoded.
+/// A QR code uses a single charset for it, ISO-8859-1 unless some byte segment needs UTF-8.
+pub(crate) fn utf8_default(segments: &[Segment]) -> bool {
+    segments.iter().any(|x| x.mode == Mode::Byte && !Mode::in_latin1(&x.data))
+}
+
+/// Split a string into the segments that gives the fewest bits.
+/// The char count len depends on the version, so does the optimal split.
+/// Byte mode data is in ISO-8859-1 if possible, otherwise in UTF-8,
+/// with the UTF-8 ECI designator before the first byte segment if `eci` is set.
+/// ```
+/// # use qrcode53bytes::*;
+/// let segments = segment::optimal("https://bestia.dev/?id=12345678901234567890", Version::new(3), true);
+/// assert_eq!(segments.len(), 2);
+/// assert_eq!(segments[1].mode, Mode::Numeric);
+/// // Kanji mode avoids the ECI designator and the UTF-8 bytes.
+/// let segments = segment::optimal("漢é", Version::new(1), true);
+/// assert_eq!(segment::bit_len(&segments, Version::new(1)), 45);
+/// ```
+pub fn optimal(s: &str, v: Version, eci: bool) -> Vec<Segment> {
+    let mut utf8 = optimal_in(s, v, true).unwrap();
+    if eci {
+        if let Some(first) = utf8.iter_mut().find(|x| x.mode == Mode::Byte) {
+            first.eci = Some(ECI_UTF8);
+        }
+    }
+    match optimal_in(s, v, false) {
+        Some(latin1) if bit_len(&latin1, v) <= bit_len(&utf8, v) => latin1,
+        _ => utf8,
+    }
+}
+
+// Optimal segments with byte mode data in a single charset, UTF-8 or ISO-8859-1.
+// Doesn't add any ECI designator. None if some char can't be encoded.
+pub(crate) fn optimal_in(s: &str, v: Version, utf8: bool) -> Option<Vec<Segment>> {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.is_empty() {
+        return Some(vec![Segment::new(Mode::from_str(s), s)]);
+    }
+    if !chars.iter().all(|c| MODES.iter().any(|x| char_cost(*x, *c, utf8).is_some())) {
+        return None;
+    }
+
+    // Dynamic programming over the chars. Costs are in 1/6 bits,
+    // as numeric and alphanumeric use fractions of bits per char.
+    // The state after a char is the mode the next char will be encoded in.
+    let head: Vec<usize> = MODES.iter().map(|x| (4 + v.char_count_len(*x)) * 6).collect();
+    let mut costs = head.clone();
+    // The mode a char is encoded in, for each state after the char.
+    let mut char_modes: Vec<[Option<usize>; 4]> = Vec::with_capacity(chars.len());
+    for c in chars.iter() {
+        // Continue the segment in the same mode.
+        let mut cur = [usize::MAX; 4];
+        let mut modes = [None; 4];
+        for (j, mode) in MODES.iter().enumerate() {
+            if let Some(cost) = char_cost(*mode, *c, utf8) {
+                cur[j] = costs[j] + cost;
+                modes[j] = Some(j);
+            }
+        }
+        // Or end the segment after this char and start a new one in another mode.
+        // A segment always ends on a whole bit.
+        let ended = cur;
+        let continued = modes;
+        for j in 0..MODES.len() {
+            for k in 0..MODES.len() {
+                if continued[k].is_none() {
+                    continue;
+                }
+                let cost = ended[k].div_ceil(6) * 6 + head[j];
+                if cost < cur[j] {
+                    cur[j] = cost;
+                    modes[j] = Some(k);
+                }
+            }
+        }
+        costs = cur.to_vec();
+        char_modes.push(modes);
+    }
+
+    // Trace backwards from the cheapest final state.
+    let mut state = (0..MODES.len()).min_by_key(|x| costs[*x]).unwrap();
+    let mut res_modes = vec![Mode::Byte; chars.len()];
+    for i in (0..chars.len()).rev() {
+        state = char_modes[i][state].unwrap();
+        res_modes[i] = MODES[state];
+    }
+
+    // Join consecutive chars with the same mode.
+    let mut res: Vec<Segment> = Vec::new();
+    for (c, mode) in chars.iter().zip(res_modes) {
+        match res.last_mut() {
+            Some(segment) if segment.mode == mode => segment.data.push(*c),
+            _ => res.push(Segment::new(mode, &c.to_string())),
+        }
+    }
+    Some(res)
+}
+
+// Cost in 1/6 bits to encode a char in a mode, None if the mode can't encode it.
+fn char_cost(mode: Mode, c: char, utf8: bool) -> Option<usize> {
+    let mut buf = [0; 4];
+    if !mode.matches(c.encode_utf8(&mut buf)) {
+        return None;
+    }
+    let cost = match mode {
+        Mode::Numeric => 20,
+        Mode::Alphanumeric => 33,
+        Mode::Kanji => 78,
+        Mode::Byte if utf8 => c.len_utf8() * 48,
+        Mode::Byte if (c as u32) <= 0xFF => 48,
+        Mode::Byte => return None,
+    };
+    Some(cost)
+}
+
+static MODES: [Mode; 4] = [Mode::Numeric, Mode::Alphanumeric, Mode::Kanji, Mode::Byte];
@@ -0,0 +1,179 @@
+//! Structured append, data split over a sequence of up to 16 QR codes.
+//!
+//! Each QR code starts with a header with its position in the sequence,
+//! the total count and a parity byte for the whole data.
+//! Scanners supporting it reassemble the data from all QR codes.
+
+use crate::builder::{Error, QrBuilder};
+use crate::data;
+use crate::ec::ECLevel;
+use crate::info;
+use crate::mode::Mode;
+use crate::qr::Qr;
+use crate::segment::{self, Segment};
+use crate::version::Version;
+
+use bitvec::prelude::*;
+
+/// Max number of QR codes in a sequence.
+pub const MAX_SEQUENCE_LEN: usize = 16;
+
+/// Len in bits of the structured append header.
+pub const HEADER_LEN: usize = 20;
+
+/// Structured append header, placed first in each QR code of a sequence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StructuredAppend {
+    /// Position in the sequence, starting at 0.
+    pub index: usize,
+    /// Number of QR codes in the sequence, 1 to 16.
+    pub total: usize,
+    /// Parity of the whole data, the same in all QR codes of the sequence.
+    pub parity: u8,
+}
+
+impl StructuredAppend {
+    /// Create a new header.
+    pub fn new(index: usize, total: usize, parity: u8) -> StructuredAppend {
+        assert!((1..=MAX_SEQUENCE_LEN).contains(&total));
+        assert!(index < total);
+        StructuredAppend { index, total, parity }
+    }
+
+    /// Returns the mode indicator 0011, the index, the total count - 1 and the parity.
+    pub fn to_bitvec(&self) -> BitVec<Msb0, u8> {
+        let mut bv = bitvec![Msb0, u8; 0, 0, 1, 1];
+        data::append(&mut bv, self.index as u32, 4);
+        data::append(&mut bv, (self.total - 1) as u32, 4);
+        data::append(&mut bv, self.parity as u32, 8);
+        bv
+    }
+}
+
+/// Returns the parity of the data, all bytes xored together.
+/// Uses ISO-8859-1 bytes if possible, otherwise UTF-8, the same as byte mode.
+pub fn parity(s: &str) -> u8 {
+    if Mode::in_latin1(s) {
+        s.chars().fold(0, |acc, x| acc ^ x as u8)
+    } else {
+        s.bytes().fold(0, |acc, x| acc ^ x)
+    }
+}
+
+/// A sequence of QR codes, with the data split using structured append.
+#[derive(Debug, PartialEq, Eq)]
+pub struct QrSequence {
+    /// The QR codes, in order.
+    pub qrs: Vec<Qr>,
+
+    /// Parity of the whole data, stored in each QR code.
+    pub parity: u8,
+}
+
+impl QrSequence {
+    /// Split a string over as few QR codes as possible, no larger than max_version.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let s = "https://bestia.dev/mem6/#p04.1234".repeat(4);
+    /// let seq = QrSequence::new(&s, Version::new(3)).unwrap();
+    /// assert_eq!(seq.len(), 3);
+    /// assert_eq!(seq.parity, sequence::parity(&s));
+    /// ```
+    pub fn new(s: &str, max_version: Version) -> Result<QrSequence, Error> {
+        QrSequence::with_ecl(s, max_version, ECLevel::L)
+    }
+
+    /// Split a string over as few QR codes as possible, no larger than max_version,
+    /// using a specific error correction level.
+    pub fn with_ecl(s: &str, max_version: Version, ecl: ECLevel) -> Result<QrSequence, Error> {
+        let chars: Vec<char> = s.chars().collect();
+        let utf8 = !Mode::in_latin1(s);
+        let parity = parity(s);
+        let capacity = info::total_bits(max_version, ecl);
+
+        // Greedily fill each QR code with as many chars as fits.
+        // The bits needed never decrease when chars are added, so binary search the len.
+        let mut chunks: Vec<&[char]> = Vec::new();
+        let mut needed = 0;
+        let mut rest = &chars[..];
+        loop {
+            let fits = |n: usize| bit_len(&rest[..n], max_version, utf8) <= capacity;
+            let (mut lo, mut hi) = (0, rest.len());
+            while lo < hi {
+                let mid = (lo + hi).div_ceil(2);
+                if fits(mid) {
+                    lo = mid;
+                } else {
+                    hi = mid - 1;
+                }
+            }
+            if lo == 0 && !rest.is_empty() {
+                // Not even a single char fits.
+                return Err(Error::MessageTooLong {
+                    needed: bit_len(&rest[..1], max_version, utf8).div_ceil(8),
+                    available: capacity / 8,
+                });
+            }
+            needed += bit_len(&rest[..lo], max_version, utf8);
+            chunks.push(&rest[..lo]);
+            rest = &rest[lo..];
+            if rest.is_empty() {
+                break;
+            }
+        }
+        if chunks.len() > MAX_SEQUENCE_LEN {
+            return Err(Error::MessageTooLong {
+                needed: needed.div_ceil(8),
+                available: MAX_SEQUENCE_LEN * info::total_codewords(max_version, ecl),
+            });
+        }
+
+        let total = chunks.len();
+        let mut qrs = Vec::with_capacity(total);
+        for (index, chunk) in chunks.iter().enumerate() {
+            // Use the smallest version the chunk fits in.
+            let (v, segments) = (1..=max_version.0)
+                .map(Version::new)
+                .map(|v| (v, segments(chunk, v, utf8)))
+                .find(|(v, x)| HEADER_LEN + segment::bit_len(x, *v) <= info::total_bits(*v, ecl))
+                .unwrap();
+            let qr = QrBuilder::new()
+                .version(v)
+                .ecl(ecl)
+                .structured_append(StructuredAppend::new(index, total, parity))
+                .into_segments(&segments)?;
+            qrs.push(qr);
+        }
+
+        Ok(QrSequence { qrs, parity })
+    }
+
+    /// Returns the number of QR codes in the sequence.
+    pub fn len(&self) -> usize {
+        self.qrs.len()
+    }
+
+    /// Returns true if the sequence has no QR codes.
+    pub fn is_empty(&self) -> bool {
+        self.qrs.is_empty()
+    }
+}
+
+// Optimal segments for a part of the data.
+// If the whole data isn't in ISO-8859-1 all byte segments use UTF-8 with an ECI,
+// so the reassembled bytes have a single encoding.
+fn segments(chars: &[char], v: Version, utf8: bool) -> Vec<Segment> {
+    let s: String = chars.iter().collect();
+    let mut segments = segment::optimal_in(&s, v, utf8).unwrap();
+    if utf8 {
+        if let Some(first) = segments.iter_mut().find(|x| x.mode == Mode::Byte) {
+            first.eci = Some(segment::ECI_UTF8);
+        }
+    }
+    segments
+}
+
+// Bits needed for a part of the data, including the header.
+fn bit_len(chars: &[char], v: Version, utf8: bool) -> usize {
+    HEADER_LEN + segment::bit_len(&segments(chars, v, utf8), v)
+}
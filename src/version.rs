@@ -1,5 +1,7 @@
 //! Specifies the version of a QR code.
 
+use crate::ec::ECLevel;
+use crate::info;
 use crate::mode::Mode;
 
 /// QR code version, defines the size
@@ -9,13 +11,44 @@ pub struct Version(pub usize);
 impl Version {
     /// Create a new version, must be in the [1..40] range.
     pub fn new(v: usize) -> Version {
-        assert!(v == 3);
+        assert!((1..=40).contains(&v));
         Version(v)
     }
 
-    /// Return the data capacity.
-    pub fn capacity(&self) -> usize {
-        53
+    /// Return the data capacity, in characters of the given mode.
+    pub fn capacity(&self, mode: Mode, ecl: ECLevel) -> usize {
+        let bits = info::total_bits(*self, ecl) - 4 - self.char_count_len(mode);
+        match mode {
+            // Groups of 3 digits are stored in 10 bits, remaining 2 digits in 7 bits and 1 digit in 4 bits.
+            Mode::Numeric => {
+                let rem = match bits % 10 {
+                    7..=9 => 2,
+                    4..=6 => 1,
+                    _ => 0,
+                };
+                (bits / 10) * 3 + rem
+            }
+            // Pairs of chars are stored in 11 bits and a remaining char in 6 bits.
+            Mode::Alphanumeric => (bits / 11) * 2 + if bits % 11 >= 6 { 1 } else { 0 },
+            Mode::Kanji => bits / 13,
+            Mode::Byte => bits / 8,
+        }
+    }
+
+    /// Return the smallest version that can hold the data.
+    /// ```
+    /// # use qrcode53bytes::*;
+    /// let v = Version::minimal("https://bestia.dev/mem6/#p04.1234", Mode::Byte, ECLevel::L);
+    /// assert_eq!(v, Some(Version::new(3)));
+    /// ```
+    pub fn minimal(s: &str, mode: Mode, ecl: ECLevel) -> Option<Version> {
+        Version::minimal_in_range(s, mode, ecl, Version::new(1), Version::new(40))
+    }
+
+    /// Return the smallest version in the [min..max] range that can hold the data.
+    pub fn minimal_in_range(s: &str, mode: Mode, ecl: ECLevel, min: Version, max: Version) -> Option<Version> {
+        let len = mode.char_count(s);
+        (min.0..=max.0).map(Version::new).find(|v| v.capacity(mode, ecl) >= len)
     }
 
     /// Return the size of the QR code.
@@ -25,13 +58,20 @@ impl Version {
 
     /// Returns the required len of the char count bit representation.
     pub fn char_count_len(&self, mode: Mode) -> usize {
-        if self.0 == 3 {
-            match mode {
-                Mode::Byte => 8,
-            }
-        } else {
-            panic!("Malformed version {}", self.0);
-        }
+        // The len only changes between the version ranges 1-9, 10-26 and 27-40.
+        let range = match self.0 {
+            1..=9 => 0,
+            10..=26 => 1,
+            27..=40 => 2,
+            _ => panic!("Malformed version {}", self.0),
+        };
+        let lens = match mode {
+            Mode::Numeric => [10, 12, 14],
+            Mode::Alphanumeric => [9, 11, 13],
+            Mode::Kanji => [8, 10, 12],
+            Mode::Byte => [8, 16, 16],
+        };
+        lens[range]
     }
 
     /// Returns true if this version requires extra version areas.
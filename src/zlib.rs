@@ -0,0 +1,169 @@
+//! Zlib compression, used by the PNG renderer.
+//!
+//! Deflate with the fixed Huffman codes and LZ77 matches from a hash chain.
+//! Not as small as a full encoder, but rendered QR codes repeat a lot,
+//! and it's small and works everywhere, wasm included.
+
+/// Compress data into a zlib stream.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    // 32K window, no preset dictionary. The header must be a multiple of 31.
+    let mut res = vec![0x78, 0x01];
+    let mut w = BitWriter::default();
+
+    // A single final block with fixed Huffman codes.
+    w.write(1, 1);
+    w.write(1, 2);
+
+    let mut head = vec![usize::MAX; HASH_SIZE];
+    let mut prev = vec![usize::MAX; data.len()];
+    let mut i = 0;
+    while i < data.len() {
+        let (len, distance) = longest_match(data, i, &head, &prev);
+        let end = if len >= MIN_MATCH { i + len } else { i + 1 };
+        if len >= MIN_MATCH {
+            write_length(&mut w, len);
+            write_distance(&mut w, distance);
+        } else {
+            write_literal(&mut w, data[i] as usize);
+        }
+        // Add all passed positions to the hash chains.
+        while i < end {
+            if i + MIN_MATCH <= data.len() {
+                let h = hash(&data[i..]);
+                prev[i] = head[h];
+                head[h] = i;
+            }
+            i += 1;
+        }
+    }
+    write_literal(&mut w, END_OF_BLOCK);
+    res.append(&mut w.finish());
+
+    res.extend_from_slice(&adler32(data).to_be_bytes());
+    res
+}
+
+/// Adler-32 checksum of data, as used by zlib.
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // Sums don't overflow for 5552 bytes, the zlib constant.
+    for chunk in data.chunks(5552) {
+        for x in chunk {
+            a += *x as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+// Deflate writes bits starting with the least significant bit of each byte.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    len: usize,
+}
+
+impl BitWriter {
+    // Write the len lowest bits of v, least significant bit first.
+    fn write(&mut self, v: u32, len: usize) {
+        self.acc |= v << self.len;
+        self.len += len;
+        while self.len >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    // Huffman codes are written most significant bit first.
+    fn write_code(&mut self, code: u32, len: usize) {
+        let reversed = (0..len).fold(0, |acc, i| acc << 1 | (code >> i) & 1);
+        self.write(reversed, len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+// The longest earlier match at i, as len and distance.
+fn longest_match(data: &[u8], i: usize, head: &[usize], prev: &[usize]) -> (usize, usize) {
+    if i + MIN_MATCH > data.len() {
+        return (0, 0);
+    }
+    let max = (data.len() - i).min(MAX_MATCH);
+    let (mut best_len, mut best_distance) = (0, 0);
+    let mut j = head[hash(&data[i..])];
+    let mut chain = 0;
+    while j != usize::MAX && i - j <= WINDOW_SIZE && chain < MAX_CHAIN {
+        let len = data[j..].iter().zip(data[i..i + max].iter()).take_while(|(a, b)| a == b).count();
+        if len > best_len {
+            best_len = len;
+            best_distance = i - j;
+            if len == max {
+                break;
+            }
+        }
+        j = prev[j];
+        chain += 1;
+    }
+    (best_len, best_distance)
+}
+
+fn hash(data: &[u8]) -> usize {
+    ((data[0] as usize) << 10 ^ (data[1] as usize) << 5 ^ data[2] as usize) % HASH_SIZE
+}
+
+// Literals and lengths share the fixed Huffman alphabet.
+fn write_literal(w: &mut BitWriter, v: usize) {
+    let v = v as u32;
+    match v {
+        0..=143 => w.write_code(0x30 + v, 8),
+        144..=255 => w.write_code(0x190 + v - 144, 9),
+        256..=279 => w.write_code(v - 256, 7),
+        _ => w.write_code(0xC0 + v - 280, 8),
+    }
+}
+
+fn write_length(w: &mut BitWriter, len: usize) {
+    let i = LENGTH_BASE.iter().rposition(|x| *x <= len).unwrap();
+    write_literal(w, 257 + i);
+    w.write((len - LENGTH_BASE[i]) as u32, LENGTH_EXTRA[i]);
+}
+
+fn write_distance(w: &mut BitWriter, distance: usize) {
+    let i = DISTANCE_BASE.iter().rposition(|x| *x <= distance).unwrap();
+    w.write_code(i as u32, 5);
+    w.write((distance - DISTANCE_BASE[i]) as u32, DISTANCE_EXTRA[i]);
+}
+
+const END_OF_BLOCK: usize = 256;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const WINDOW_SIZE: usize = 32768;
+const HASH_SIZE: usize = 1 << 15;
+// How many earlier positions with the same hash to try.
+const MAX_CHAIN: usize = 64;
+
+// Smallest length of length codes 257 to 285, and their extra bits.
+static LENGTH_BASE: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+static LENGTH_EXTRA: [usize; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+// Smallest distance of distance codes 0 to 29, and their extra bits.
+static DISTANCE_BASE: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385,
+    24577,
+];
+static DISTANCE_EXTRA: [usize; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];